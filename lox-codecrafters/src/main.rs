@@ -1,39 +1,161 @@
 use std::env;
 use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Write;
 use std::process::exit;
-use lox_runtime::interpreter::Interpreter;
+use lox_runtime::interpreter::{Interpreter, InterpreterBuilder, EXECUTION_LIMIT_EXCEEDED, WRITE_OUTPUT_FAILED};
 use lox_syntax::parser::Parser;
+use lox_syntax::resolver::Resolver;
+use lox_syntax::token::Token;
 use lox_syntax::tokenizer::Scanner;
 
+enum Output {
+    Stdout,
+    Stderr,
+    File(String),
+}
+
+impl Output {
+    fn open(&self) -> io::Result<Box<dyn Write>> {
+        Ok(match self {
+            Output::Stdout => Box::new(io::stdout()),
+            Output::Stderr => Box::new(io::stderr()),
+            Output::File(path) => Box::new(File::create(path)?),
+        })
+    }
+}
+
+enum Input {
+    File(String),
+    Source(String),
+}
+
+impl Input {
+    fn read(&self) -> Vec<u8> {
+        match self {
+            Input::File(filename) => fs::read(filename).unwrap_or_else(|_| {
+                eprintln!("Failed to read file {}", filename);
+                Vec::new()
+            }),
+            Input::Source(source) => source.clone().into_bytes(),
+        }
+    }
+}
+
+fn open_scanner(source: &[u8]) -> Scanner<'_> {
+    Scanner::from_bytes(source).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        exit(65);
+    })
+}
+
+struct Options {
+    input: Input,
+    output: Output,
+    warn_unused: bool,
+    max_steps: Option<usize>,
+    tree: bool,
+    call_main: bool,
+    json: bool,
+}
+
+// Every option, with the commands it applies to
+const FLAGS: [(&str, &[&str]); 7] = [
+    ("-e", &["run"]),
+    ("--output", &["run"]),
+    ("--warn-unused", &["check", "run"]),
+    ("--max-steps", &["run"]),
+    ("--call-main", &["run"]),
+    ("--tree", &["parse"]),
+    ("--json", &["tokenize"]),
+];
+
+impl Options {
+    fn parse(command: &str, args: &[String]) -> Result<Options, String> {
+        let mut input: Option<Input> = None;
+        let mut output = Output::Stdout;
+        let mut warn_unused = false;
+        let mut max_steps: Option<usize> = None;
+        let mut tree = false;
+        let mut call_main = false;
+        let mut json = false;
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if arg.starts_with('-') {
+                match FLAGS.iter().find(|(flag, _)| flag == arg) {
+                    Some((_, commands)) if !commands.contains(&command) => return Err(format!("Option {} is not supported by {}", arg, command)),
+                    Some(_) => {},
+                    None => return Err(format!("Unknown option {}", arg)),
+                }
+            }
+
+            match arg.as_str() {
+                "--output" => output = match args.next().map(|value| value.as_str()) {
+                    Some("stdout") => Output::Stdout,
+                    Some("stderr") => Output::Stderr,
+                    Some(path) => Output::File(path.to_string()),
+                    None => return Err("Missing value for --output".to_string()),
+                },
+                "-e" if input.is_none() => input = match args.next() {
+                    Some(source) => Some(Input::Source(source.clone())),
+                    None => return Err("Missing value for -e".to_string()),
+                },
+                "--warn-unused" => warn_unused = true,
+                "--tree" => tree = true,
+                "--call-main" => call_main = true,
+                "--json" => json = true,
+                "--max-steps" => max_steps = match args.next().map(|value| value.parse::<usize>()) {
+                    Some(Ok(limit)) => Some(limit),
+                    Some(Err(_)) => return Err("Invalid value for --max-steps".to_string()),
+                    None => return Err("Missing value for --max-steps".to_string()),
+                },
+                _ if input.is_none() => input = Some(Input::File(arg.clone())),
+                _ => return Err(format!("Unexpected argument {}", arg)),
+            }
+        }
+
+        match input {
+            Some(input) => Ok(Options { input, output, warn_unused, max_steps, tree, call_main, json }),
+            None => Err("Missing filename".to_string()),
+        }
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     if args.len() < 3 {
-        eprintln!("Usage: {} tokenize <filename>", args[0]);
-        eprintln!("Usage: {} parse <filename>", args[0]);
+        eprintln!("Usage: {} tokenize [--json] <filename>", args[0]);
+        eprintln!("Usage: {} parse [--tree] <filename>", args[0]);
         eprintln!("Usage: {} evaluate <filename>", args[0]);
-        eprintln!("Usage: {} run <filename>", args[0]);
+        eprintln!("Usage: {} check [--warn-unused] <filename>", args[0]);
+        eprintln!("Usage: {} run [--output <stdout|stderr|path>] [--warn-unused] [--max-steps <n>] [--call-main] <filename | -e <source>>", args[0]);
         return;
     }
 
     let command = &args[1];
-    let filename = &args[2];
-
-    let file_contents = fs::read_to_string(filename).unwrap_or_else(|_| {
-        eprintln!("Failed to read file {}", filename);
-        String::new()
+    let options = Options::parse(command, &args[2..]).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        exit(64);
     });
+    let file_contents = options.input.read();
 
     match command.as_str() {
         "tokenize" => {
-            let mut scanner = Scanner::new(&file_contents);
+            let mut scanner = open_scanner(&file_contents);
             let (tokens, errors) = scanner.scan_tokens();
 
             for error in errors.iter() {
                 eprintln!("{}", error);
             }
 
-            for token in tokens {
-                println!("{}", token);
+            if options.json {
+                println!("[{}]", tokens.iter().map(Token::to_json).collect::<Vec<String>>().join(",\n "));
+            } else {
+                for token in tokens {
+                    println!("{}", token);
+                }
             }
 
             if !errors.is_empty() {
@@ -41,7 +163,7 @@ fn main() {
             }
         },
         "parse" => {
-            let mut scanner = Scanner::new(&file_contents);
+            let mut scanner = open_scanner(&file_contents);
             let (tokens, errors) = scanner.scan_tokens();
 
             for error in errors.iter() {
@@ -52,18 +174,42 @@ fn main() {
                 exit(65);
             }
 
+            // An empty program has nothing to print
+            if tokens.len() == 1 {
+                return;
+            }
+
             let mut parser = Parser::new(tokens);
+
+            // The tree view shows whole programs, the default output is a single expression
+            if options.tree {
+                match parser.parse() {
+                    Ok(statements) => {
+                        for statement in statements {
+                            println!("{}", statement.pretty(0));
+                        }
+                    },
+                    Err(error) => {
+                        eprintln!("{}", error);
+                        exit(65);
+                    }
+                }
+
+                return;
+            }
+
             let expression = parser.parse_expression();
 
-            if expression.is_ok() {
-                println!("{}", expression.unwrap());
-            } else {
-                eprintln!("{}", expression.err().unwrap());
-                exit(65);
+            match expression {
+                Ok(expression) => println!("{}", expression),
+                Err(error) => {
+                    eprintln!("{}", error);
+                    exit(65);
+                }
             }
         },
         "evaluate" => {
-            let mut scanner = Scanner::new(&file_contents);
+            let mut scanner = open_scanner(&file_contents);
             let (tokens, errors) = scanner.scan_tokens();
 
             for error in errors.iter() {
@@ -77,23 +223,53 @@ fn main() {
             let mut parser = Parser::new(tokens);
             let expression = parser.parse_expression();
 
-            if expression.is_ok() {
-                let mut interpreter = Interpreter::new(|_|{});
-                let result = interpreter.evaluate_expression(&expression.unwrap());
+            match expression {
+                Ok(expression) => {
+                    let mut interpreter = Interpreter::new(|_|{});
 
-                if result.is_ok() {
-                    println!("{}", result.unwrap());
-                } else {
-                    eprintln!("{}", result.err().unwrap());
-                    exit(70);
+                    match interpreter.evaluate_expression(&expression) {
+                        Ok(value) => println!("{}", value),
+                        Err(error) => {
+                            eprintln!("{}", error);
+                            exit(70);
+                        }
+                    }
+                },
+                Err(error) => {
+                    eprintln!("{}", error);
+                    exit(65);
                 }
-            } else {
-                eprintln!("{}", expression.err().unwrap());
+            }
+        },
+        "check" => {
+            let mut scanner = open_scanner(&file_contents);
+            let (tokens, errors) = scanner.scan_tokens();
+
+            for error in errors.iter() {
+                eprintln!("{}", error);
+            }
+
+            if !errors.is_empty() {
+                exit(65);
+            }
+
+            let mut parser = Parser::new(tokens);
+            let (statements, errors) = parser.parse_all();
+
+            for error in errors.iter() {
+                eprintln!("{}", error);
+            }
+
+            if !errors.is_empty() {
                 exit(65);
             }
+
+            for warning in Resolver::new().warn_unused(options.warn_unused).resolve(&statements) {
+                eprintln!("Warning: {}", warning);
+            }
         },
         "run" => {
-            let mut scanner = Scanner::new(&file_contents);
+            let mut scanner = open_scanner(&file_contents);
             let (tokens, errors) = scanner.scan_tokens();
 
             for error in errors.iter() {
@@ -105,21 +281,51 @@ fn main() {
             }
 
             let mut parser = Parser::new(tokens);
-            let statements = parser.parse();
+            let (statements, errors) = parser.parse_all();
 
-            if statements.is_ok() {
-                let mut interpreter = Interpreter::new(|value| println!("{}", value));
-                let result = interpreter.run(&statements.unwrap());
+            for error in errors.iter() {
+                eprintln!("{}", error);
+            }
 
-                if result.is_ok() {
-                } else {
-                    eprintln!("{}", result.err().unwrap());
-                    exit(70);
-                }
-            } else {
-                eprintln!("{}", statements.err().unwrap());
+            if !errors.is_empty() {
                 exit(65);
             }
+
+            for warning in Resolver::new().warn_unused(options.warn_unused).resolve(&statements) {
+                eprintln!("Warning: {}", warning);
+            }
+
+            let output = options.output.open().unwrap_or_else(|error| {
+                eprintln!("Failed to open output: {}", error);
+                exit(74);
+            });
+            let mut builder = InterpreterBuilder::new();
+            if let Some(limit) = options.max_steps {
+                builder = builder.max_steps(limit);
+            }
+
+            let mut interpreter = builder.writer(output);
+
+            #[cfg(feature = "counters")]
+            lox_runtime::counters::reset();
+
+            let mut result = interpreter.run(&statements);
+
+            if result.is_ok() && options.call_main {
+                result = interpreter.call_main().map(|_| ());
+            }
+
+            #[cfg(feature = "counters")]
+            eprintln!("{}", lox_runtime::counters::snapshot());
+
+            if let Err(error) = result {
+                eprintln!("{}", error);
+                exit(match error.as_str() {
+                    EXECUTION_LIMIT_EXCEEDED => 75,
+                    error if error.starts_with(WRITE_OUTPUT_FAILED) => 74,
+                    _ => 70,
+                });
+            }
         },
         _ => {
             eprintln!("Unknown command: {}", command);
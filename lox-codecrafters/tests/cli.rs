@@ -0,0 +1,236 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("lox-cli-{}-{}", std::process::id(), name))
+}
+
+fn run_lox(name: &str, source: &str, args: &[&str]) -> Output {
+    let path = temp_path(&format!("{}.lox", name));
+    fs::write(&path, source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_lox-codecrafters"))
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+
+    fs::remove_file(&path).unwrap();
+    output
+}
+
+#[test]
+fn test_cli_unknown_option() {
+    let output = run_lox("unknown_option", "print 1;", &["tokenize", "--jsn"]);
+
+    assert_eq!(Some(64), output.status.code());
+    assert!(output.stdout.is_empty());
+    assert_eq!("Unknown option --jsn\n", String::from_utf8(output.stderr).unwrap());
+}
+
+#[test]
+fn test_cli_option_for_other_command() {
+    let cases = [(&["tokenize", "--max-steps", "5"][..], "Option --max-steps is not supported by tokenize\n"), (&["parse", "--call-main"][..], "Option --call-main is not supported by parse\n")];
+
+    for (args, expected) in cases {
+        let output = run_lox("option_for_other_command", "print 1;", args);
+
+        assert_eq!(Some(64), output.status.code());
+        assert_eq!(expected, String::from_utf8(output.stderr).unwrap());
+    }
+}
+
+#[test]
+fn test_cli_run_output_write_error() {
+    let output = run_lox("output_write_error", "print 1;\nprint 2;", &["run", "--output", "/dev/full"]);
+
+    assert_eq!(Some(74), output.status.code());
+    assert!(String::from_utf8(output.stderr).unwrap().starts_with("Failed to write output: "));
+}
+
+#[test]
+fn test_cli_run_output_file() {
+    let output_path = temp_path("output.txt");
+    let output = run_lox("output_file", "print 1;\nprint \"two\";\nprint 1 + 2;", &["run", "--output", output_path.to_str().unwrap()]);
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!("1\ntwo\n3\n", fs::read_to_string(&output_path).unwrap());
+
+    fs::remove_file(&output_path).unwrap();
+}
+
+#[test]
+fn test_cli_run_output_stderr() {
+    let output = run_lox("output_stderr", "print \"hello\";", &["run", "--output", "stderr"]);
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!("hello\n", String::from_utf8(output.stderr).unwrap());
+}
+
+#[test]
+fn test_cli_run_warn_unused() {
+    let output = run_lox("warn_unused", "{ var unused = 1; var used = 2; print used; }", &["run", "--warn-unused"]);
+
+    assert!(output.status.success());
+    assert_eq!("2\n", String::from_utf8(output.stdout).unwrap());
+    assert_eq!("Warning: Unused variable 'unused'.\n", String::from_utf8(output.stderr).unwrap());
+}
+
+#[test]
+fn test_cli_run_max_steps() {
+    let output = run_lox("max_steps", "print \"start\";\nwhile (true) {}", &["run", "--max-steps", "100"]);
+
+    assert_eq!(Some(75), output.status.code());
+    assert_eq!("start\n", String::from_utf8(output.stdout).unwrap());
+    assert_eq!("Execution limit exceeded.\n", String::from_utf8(output.stderr).unwrap());
+}
+
+#[test]
+fn test_cli_run_source_argument() {
+    let output = Command::new(env!("CARGO_BIN_EXE_lox-codecrafters"))
+        .args(["run", "-e", "print 1 + 1; print \"two\";"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!("2\ntwo\n", String::from_utf8(output.stdout).unwrap());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_cli_run_call_main() {
+    let output = run_lox("call_main", "fun main() { print \"main\"; }\nprint \"top\";", &["run", "--call-main"]);
+
+    assert!(output.status.success());
+    assert_eq!("top\nmain\n", String::from_utf8(output.stdout).unwrap());
+
+    let output = run_lox("call_main_missing", "fun helper() {}", &["run", "--call-main"]);
+
+    assert_eq!(Some(70), output.status.code());
+    assert_eq!("No 'main' function defined.\n", String::from_utf8(output.stderr).unwrap());
+
+    let output = run_lox("call_main_disabled", "fun main() { print \"main\"; }", &["run"]);
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_cli_invalid_utf8() {
+    let path = temp_path("invalid_utf8.lox");
+    fs::write(&path, b"print 1;\nprint \"\xff\";").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_lox-codecrafters"))
+        .args(["run"])
+        .arg(&path)
+        .output()
+        .unwrap();
+
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(Some(65), output.status.code());
+    assert!(output.stdout.is_empty());
+    assert_eq!("[line 2] Error: Source is not valid UTF-8.\n", String::from_utf8(output.stderr).unwrap());
+}
+
+#[test]
+fn test_cli_tokenize_json() {
+    let output = run_lox("tokenize_json", "var a = 1.5;\nprint \"C:\\dir\";", &["tokenize", "--json"]);
+
+    assert!(output.status.success());
+    assert_eq!(concat!(
+        "[{\"type\": \"VAR\", \"lexeme\": \"var\", \"line\": 1, \"value\": null},\n",
+        " {\"type\": \"IDENTIFIER\", \"lexeme\": \"a\", \"line\": 1, \"value\": null},\n",
+        " {\"type\": \"EQUAL\", \"lexeme\": \"=\", \"line\": 1, \"value\": null},\n",
+        " {\"type\": \"NUMBER\", \"lexeme\": \"1.5\", \"line\": 1, \"value\": 1.5},\n",
+        " {\"type\": \"SEMICOLON\", \"lexeme\": \";\", \"line\": 1, \"value\": null},\n",
+        " {\"type\": \"PRINT\", \"lexeme\": \"print\", \"line\": 2, \"value\": null},\n",
+        " {\"type\": \"STRING\", \"lexeme\": \"\\\"C:\\\\dir\\\"\", \"line\": 2, \"value\": \"C:\\\\dir\"},\n",
+        " {\"type\": \"SEMICOLON\", \"lexeme\": \";\", \"line\": 2, \"value\": null},\n",
+        " {\"type\": \"EOF\", \"lexeme\": \"\", \"line\": 2, \"value\": null}]\n",
+    ), String::from_utf8(output.stdout).unwrap());
+}
+
+#[test]
+fn test_cli_check_valid() {
+    let output = run_lox("check_valid", "print \"never printed\";\nwhile (true) {}", &["check"]);
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_cli_check_invalid() {
+    let output = run_lox("check_invalid", "print 1;\nprint (1;", &["check"]);
+
+    assert_eq!(Some(65), output.status.code());
+    assert!(output.stdout.is_empty());
+    assert_eq!("[line 2] Error at ';': Expect expression.\n", String::from_utf8(output.stderr).unwrap());
+}
+
+#[test]
+fn test_cli_check_multiple_errors() {
+    let output = run_lox("check_multiple_errors", "var = 1;\nprint 1;\nprint 2 +;", &["check"]);
+
+    assert_eq!(Some(65), output.status.code());
+    assert!(output.stdout.is_empty());
+    assert_eq!("[line 1] Error at '=': Expect variable name.\n[line 3] Error at ';': Expect expression.\n", String::from_utf8(output.stderr).unwrap());
+}
+
+#[test]
+fn test_cli_check_scan_errors() {
+    let output = run_lox("check_scan_errors", "@\n#", &["check"]);
+
+    assert_eq!(Some(65), output.status.code());
+    assert_eq!("[line 1] Error: Unexpected character: @\n[line 2] Error: Unexpected character: #\n", String::from_utf8(output.stderr).unwrap());
+}
+
+#[test]
+fn test_cli_run_output_default() {
+    let output = run_lox("output_default", "print \"hello\";", &["run"]);
+
+    assert!(output.status.success());
+    assert_eq!("hello\n", String::from_utf8(output.stdout).unwrap());
+}
+
+#[test]
+fn test_cli_empty_programs() {
+    for (name, source) in [("empty", ""), ("whitespace", "  \n\t\r\n"), ("comments", "// line comment\n/* block\ncomment */")] {
+        let output = run_lox(name, source, &["run"]);
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+        assert!(output.stderr.is_empty());
+
+        let output = run_lox(name, source, &["parse"]);
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+        assert!(output.stderr.is_empty());
+
+        let output = run_lox(name, source, &["evaluate"]);
+        assert_eq!(Some(65), output.status.code());
+        assert!(output.stdout.is_empty());
+        assert!(String::from_utf8(output.stderr).unwrap().ends_with("Error at end: Expect expression.\n"));
+    }
+}
+
+#[test]
+fn test_cli_run_reports_all_parse_errors() {
+    let output = run_lox("run_parse_errors", "print 1;\nvar = 2;\nprint 3 +;", &["run"]);
+
+    assert_eq!(Some(65), output.status.code());
+    assert!(output.stdout.is_empty());
+    assert_eq!("[line 2] Error at '=': Expect variable name.\n[line 3] Error at ';': Expect expression.\n", String::from_utf8(output.stderr).unwrap());
+}
+
+#[test]
+fn test_cli_parse_tree() {
+    let output = run_lox("parse_tree", "var a = 1;\n{\n  print a;\n}", &["parse", "--tree"]);
+
+    assert!(output.status.success());
+    assert_eq!("var a = 1.0\nblock\n  print (variable a)\n", String::from_utf8(output.stdout).unwrap());
+}
@@ -0,0 +1,33 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use lox_runtime::interpreter::Interpreter;
+use lox_syntax::parser::Parser;
+use lox_syntax::statement::Statement;
+use lox_syntax::tokenizer::Scanner;
+
+fn parse(source: &str) -> Vec<Statement> {
+    let mut scanner = Scanner::new(source);
+    let (tokens, _) = scanner.scan_tokens();
+    Parser::new(tokens).parse().unwrap()
+}
+
+fn run(statements: &Vec<Statement>) {
+    let mut interpreter = Interpreter::new(|value| {
+        black_box(value);
+    });
+    interpreter.run(statements).unwrap();
+}
+
+fn fib_recursive(c: &mut Criterion) {
+    let statements = parse("fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); } print fib(25);");
+
+    c.bench_function("fib recursive 25", |b| b.iter(|| run(&statements)));
+}
+
+fn loop_iterative(c: &mut Criterion) {
+    let statements = parse("var a = 0; var b = 1; for (var i = 0; i < 10000; i = i + 1) { var t = a + b; a = b; b = t; } print a;");
+
+    c.bench_function("loop iterative 10000", |b| b.iter(|| run(&statements)));
+}
+
+criterion_group!(benches, fib_recursive, loop_iterative);
+criterion_main!(benches);
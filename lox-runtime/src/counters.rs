@@ -0,0 +1,68 @@
+use std::cell::Cell;
+use std::fmt::Display;
+
+// Work done by the interpreter, only tracked when built with the `counters` feature
+#[derive(Default, PartialEq, Debug, Clone, Copy)]
+pub struct Counters {
+    pub value_clones: usize,
+    pub environments: usize,
+}
+
+impl Display for Counters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Value clones: {}, environments: {}", self.value_clones, self.environments)
+    }
+}
+
+thread_local! {
+    static COUNTERS: Cell<Counters> = Cell::new(Counters::default());
+}
+
+pub fn snapshot() -> Counters {
+    COUNTERS.with(|counters| counters.get())
+}
+
+pub fn reset() {
+    COUNTERS.with(|counters| counters.set(Counters::default()));
+}
+
+pub(crate) fn count_value_clone() {
+    COUNTERS.with(|counters| counters.set(Counters { value_clones: counters.get().value_clones + 1, ..counters.get() }));
+}
+
+pub(crate) fn count_environment() {
+    COUNTERS.with(|counters| counters.set(Counters { environments: counters.get().environments + 1, ..counters.get() }));
+}
+
+#[cfg(test)]
+mod tests {
+    use lox_syntax::parser::Parser;
+    use lox_syntax::tokenizer::Scanner;
+    use crate::counters::{reset, snapshot, Counters};
+    use crate::interpreter::Interpreter;
+
+    fn run_counted(source: &str) -> Counters {
+        let mut scanner = Scanner::new(source);
+        let (tokens, _) = scanner.scan_tokens();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let mut interpreter = Interpreter::new(|_|{});
+
+        // Setting up the globals is not part of the run
+        reset();
+        interpreter.run(&statements).unwrap();
+        snapshot()
+    }
+
+    #[test]
+    fn test_counters_empty_program() {
+        assert_eq!(Counters::default(), run_counted(""));
+    }
+
+    #[test]
+    fn test_counters_program() {
+        let counters = run_counted("fun add(a, b) { return a + b; } var x = 1; for (var i = 0; i < 3; i = i + 1) { x = add(x, i); } print x;");
+
+        assert!(counters.value_clones > 0);
+        assert!(counters.environments > 0);
+    }
+}
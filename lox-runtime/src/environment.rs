@@ -6,12 +6,15 @@ use crate::value::{Error, Value};
 
 #[derive(Default, PartialEq, Debug, Clone)]
 pub struct Environment {
-    values: HashMap<String, Value>,
+    values: HashMap<String, Option<Value>>,
     enclosing: Option<Rc<RefCell<Environment>>>,
 }
 
 impl Environment {
     pub fn new_with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        #[cfg(feature = "counters")]
+        crate::counters::count_environment();
+
         Self {
             values: HashMap::new(),
             enclosing: Some(enclosing),
@@ -19,26 +22,51 @@ impl Environment {
     }
 
     pub fn declare(&mut self, name: String, value: Value) {
-        self.values.insert(name, value);
+        self.values.insert(name, Some(value));
+    }
+
+    pub fn declare_uninitialized(&mut self, name: String) {
+        self.values.insert(name, None);
+    }
+
+    pub fn declare_global(&mut self, name: String, value: Value) {
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().declare_global(name, value),
+            None => self.declare(name, value),
+        }
     }
 
     pub fn assign(&mut self, name: String, value: Value) -> Result<(), Error> {
         if let Occupied(mut entry) = self.values.entry(name.clone()) {
-            entry.insert(value);
+            entry.insert(Some(value));
             Ok(())
-        } else if self.enclosing.is_some() {
-            self.enclosing.as_ref().unwrap().borrow_mut().assign(name, value)?;
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().assign(name, value)?;
             Ok(())
         } else {
             Err(Error::Runtime(format!("Undefined variable '{}'.", name)))
         }
     }
 
+    // Only the bindings of this frame that hold a value, in no particular order
+    pub fn locals(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.values.iter().filter_map(|(name, value)| value.as_ref().map(|value| (name, value)))
+    }
+
     pub fn get(&self, name: &str) -> Result<Value, Error> {
-        if self.values.contains_key(name) {
-            Ok(self.values.get(name).unwrap().clone())
-        } else if self.enclosing.is_some() {
-            Ok(self.enclosing.as_ref().unwrap().borrow().get(name)?)
+        if let Some(value) = self.values.get(name) {
+            match value {
+                Some(value) => {
+                    // Every read of a variable copies its value out of the environment
+                    #[cfg(feature = "counters")]
+                    crate::counters::count_value_clone();
+
+                    Ok(value.clone())
+                },
+                None => Err(Error::Runtime(format!("Variable '{}' used before assignment.", name))),
+            }
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow().get(name)
         } else {
             Err(Error::Runtime(format!("Undefined variable '{}'.", name)))
         }
@@ -102,6 +130,52 @@ mod tests {
         assert!(env.assign("name".to_string(), value.clone()).is_err());
     }
 
+    #[rstest]
+    #[case(Value::String("string".into()))]
+    #[case(Value::Number(432.1))]
+    #[case(Value::Bool(true))]
+    #[case(Value::None)]
+    fn test_environment_declare_uninitialized(#[case] value: Value) {
+        let mut env = Environment::default();
+        env.declare_uninitialized("name".to_string());
+        assert!(env.get("name").is_err());
+        assert!(env.assign("name".to_string(), value.clone()).is_ok());
+        assert_eq!(value, env.get("name").unwrap());
+    }
+
+    #[test]
+    fn test_environment_declare_global() {
+        let mut env = Environment::default();
+        env.declare("name".to_string(), Value::Number(1.0));
+        let env = Rc::new(RefCell::new(env));
+        let mut env2 = Environment::new_with_enclosing(Rc::clone(&env));
+        env2.declare("name".to_string(), Value::Number(2.0));
+        env2.declare_global("name".to_string(), Value::Number(3.0));
+        assert_eq!(Value::Number(2.0), env2.get("name").unwrap());
+        assert_eq!(Value::Number(3.0), env.borrow().get("name").unwrap());
+    }
+
+    #[test]
+    fn test_environment_locals() {
+        let mut env = Environment::default();
+        env.declare("outer".to_string(), Value::Bool(true));
+        let env = Rc::new(RefCell::new(env));
+        let mut env2 = Environment::new_with_enclosing(Rc::clone(&env));
+        env2.declare("a".to_string(), Value::Number(1.0));
+        env2.declare("b".to_string(), Value::String("two".into()));
+        env2.declare("c".to_string(), Value::None);
+        env2.declare_uninitialized("d".to_string());
+
+        let mut locals: Vec<(&String, &Value)> = env2.locals().collect();
+        locals.sort_by(|left, right| left.0.cmp(right.0));
+
+        assert_eq!(vec![
+            (&"a".to_string(), &Value::Number(1.0)),
+            (&"b".to_string(), &Value::String("two".into())),
+            (&"c".to_string(), &Value::None),
+        ], locals);
+    }
+
     #[test]
     fn test_environment_get_without_declare() {
         let env = Environment::default();
@@ -1,55 +1,193 @@
 ﻿use std::cell::RefCell;
+use std::io::Write;
 use std::rc::Rc;
-use std::time::{SystemTime, UNIX_EPOCH};
 use lox_syntax::expression::{BinaryOperation, Expression, UnaryOperation};
+use lox_syntax::parser::{Parser, ReplInput};
 use lox_syntax::statement::Statement;
+use lox_syntax::tokenizer::Scanner;
+use lox_syntax::visitor::{walk_statement, ExpressionVisitor, StatementVisitor};
 use crate::environment::Environment;
+use crate::natives;
 use crate::value::{Callable, Error, Value};
 
+pub const EXECUTION_LIMIT_EXCEEDED: &str = "Execution limit exceeded.";
+pub const WRITE_OUTPUT_FAILED: &str = "Failed to write output";
+
+#[derive(Default)]
+pub struct InterpreterBuilder {
+    recursion_limit: Option<usize>,
+    max_steps: Option<usize>,
+    ordered_booleans: bool,
+    strict_nan: bool,
+    strict_boolean_logic: bool,
+    strict_uninitialized: bool,
+}
+
+impl InterpreterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn recursion_limit(mut self, limit: usize) -> Self {
+        self.recursion_limit = Some(limit);
+        self
+    }
+
+    pub fn max_steps(mut self, limit: usize) -> Self {
+        self.max_steps = Some(limit);
+        self
+    }
+
+    // Allows relational operators on two booleans, ordering false before true
+    pub fn ordered_booleans(mut self, enabled: bool) -> Self {
+        self.ordered_booleans = enabled;
+        self
+    }
+
+    // Turns arithmetic that produces NaN into a runtime error instead of a value
+    pub fn strict_nan(mut self, enabled: bool) -> Self {
+        self.strict_nan = enabled;
+        self
+    }
+
+    // Makes `and` and `or` return the truthiness of the deciding operand instead of the operand itself
+    pub fn strict_boolean_logic(mut self, enabled: bool) -> Self {
+        self.strict_boolean_logic = enabled;
+        self
+    }
+
+    // Makes reading a variable declared without initializer an error until it is assigned, instead of nil
+    pub fn strict_uninitialized(mut self, enabled: bool) -> Self {
+        self.strict_uninitialized = enabled;
+        self
+    }
+
+    pub fn build<F: FnMut(String)>(self, print: F) -> Interpreter<F> {
+        Interpreter {
+            environment: Rc::new(RefCell::new(global_environment())),
+            print,
+            recursion_limit: self.recursion_limit,
+            depth: 0,
+            max_steps: self.max_steps,
+            steps: 0,
+            ordered_booleans: self.ordered_booleans,
+            strict_nan: self.strict_nan,
+            strict_boolean_logic: self.strict_boolean_logic,
+            strict_uninitialized: self.strict_uninitialized,
+            write_error: Rc::new(RefCell::new(None)),
+            natives: natives::Context::default(),
+        }
+    }
+
+    // Builds an interpreter whose prints are collected into the returned output
+    pub fn collecting(self) -> (Interpreter<impl FnMut(String)>, Output) {
+        let output: Output = Rc::new(RefCell::new(Vec::new()));
+        let prints = Rc::clone(&output);
+
+        (self.build(move |value| prints.borrow_mut().push(value)), output)
+    }
+
+    // Builds an interpreter printing to the writer, write failures are stashed by the print closure and raised by the print statement as runtime errors
+    pub fn writer<W: Write>(self, mut writer: W) -> Interpreter<impl FnMut(String)> {
+        let write_error = Rc::new(RefCell::new(None));
+        let errors = Rc::clone(&write_error);
+
+        let mut interpreter = self.build(move |value| {
+            if let Err(error) = writeln!(writer, "{}", value) {
+                *errors.borrow_mut() = Some(format!("{}: {}", WRITE_OUTPUT_FAILED, error));
+            }
+        });
+        interpreter.write_error = write_error;
+        interpreter
+    }
+}
+
+pub type Output = Rc<RefCell<Vec<String>>>;
+
+fn check_arity(callable: &Callable, count: usize) -> Result<(), Error> {
+    match callable {
+        Callable::Variadic(minimum, _) if count < *minimum => Err(Error::Runtime(format!("Expected at least {} arguments but got {}.", minimum, count))),
+        Callable::Variadic(_, _) => Ok(()),
+        callable if callable.arity() != count => Err(Error::Runtime(format!("Expected {} arguments but got {}.", callable.arity(), count))),
+        _ => Ok(()),
+    }
+}
+
+fn global_environment() -> Environment {
+    let mut environment = Environment::default();
+    natives::declare(&mut environment);
+    environment
+}
+
 pub struct Interpreter<F: FnMut(String)> {
     environment: Rc<RefCell<Environment>>,
-    print: F
+    print: F,
+    recursion_limit: Option<usize>,
+    depth: usize,
+    max_steps: Option<usize>,
+    steps: usize,
+    ordered_booleans: bool,
+    strict_nan: bool,
+    strict_boolean_logic: bool,
+    strict_uninitialized: bool,
+    write_error: Rc<RefCell<Option<String>>>,
+    natives: natives::Context,
 }
 
 impl<F: FnMut(String)> Interpreter<F> {
     pub fn new(print: F) -> Self {
-        let mut environment = Environment::default();
-
-        environment.declare("clock".to_string(), Value::Callable(
-            Callable::Native(0, Box::new(|_args| {
-                Value::Number(match SystemTime::now().duration_since(UNIX_EPOCH) {
-                    Ok(duration) => duration.as_secs_f64().floor(),
-                    Err(_) => 0.0,
-                })
-            }))
-        ));
-        
-        Self {
-            environment: Rc::new(RefCell::new(environment)),
-            print
-        }
+        InterpreterBuilder::new().build(print)
     }
 
     pub fn run(&mut self, statements: &Vec<Statement>) -> Result<(), String> {
-        match self.run_statements(statements) {
-            Ok(value) => Ok(value),
-            Err(error) => match error {
-                Error::Runtime(error) => Err(error),
-                Error::Return(_) => Err("Received unexpected return value".to_string()),
-            }
+        if let Some(line) = find_top_level_return(statements) {
+            return Err(format!("[line {}] Error at 'return': Can't return from top-level code.", line));
         }
+
+        self.run_statements(statements).map_err(|error| error.to_string())
     }
 
-    pub fn evaluate_expression(&mut self, expression: &Expression) -> Result<Value, String> {
-        match self.evaluate(expression) {
-            Ok(value) => Ok(value),
-            Err(error) => match error {
-                Error::Runtime(error) => Err(error),
-                Error::Return(_) => Err("Received unexpected return value".to_string()),
-            }
+    // Drops every global declared by earlier runs, keeping only the natives
+    pub fn reset_globals(&mut self) {
+        self.environment = Rc::new(RefCell::new(global_environment()));
+    }
+
+    // Evaluates a single expression against the current globals, e.g. for debugger watches
+    pub fn evaluate_source(&mut self, source: &str) -> Result<Value, String> {
+        let mut scanner = Scanner::new(source);
+        let (tokens, errors) = scanner.scan_tokens();
+
+        if let Some(error) = errors.into_iter().next() {
+            return Err(error);
+        }
+
+        match Parser::new(tokens).parse_repl().map_err(|error| error.to_string())? {
+            ReplInput::Expression(expression) => self.evaluate_expression(&expression),
+            ReplInput::Statements(statements) => match statements.as_slice() {
+                [Statement::Expression(expression)] => self.evaluate_expression(expression),
+                _ => Err("Expect a single expression.".to_string()),
+            },
         }
     }
 
+    pub fn evaluate_expression(&mut self, expression: &Expression) -> Result<Value, String> {
+        self.evaluate(expression).map_err(|error| error.to_string())
+    }
+
+    pub fn call_value(&mut self, callee: Value, arguments: Vec<Value>) -> Result<Value, String> {
+        self.call(callee, arguments, None).map_err(|error| error.to_string())
+    }
+
+    // Calls the global `main` function without arguments, for programs that only declare at the top level
+    pub fn call_main(&mut self) -> Result<Value, String> {
+        let main = match self.environment.borrow().get("main") {
+            Ok(main @ Value::Callable(_)) => main,
+            _ => return Err("No 'main' function defined.".to_string()),
+        };
+
+        self.call_value(main, Vec::new())
+    }
+
     fn run_statements(&mut self, statements: &Vec<Statement>) -> Result<(), Error> {
         for statement in statements {
             self.run_statement(statement)?;
@@ -59,22 +197,44 @@ impl<F: FnMut(String)> Interpreter<F> {
     }
 
     fn run_statement(&mut self, statement: &Statement) -> Result<(), Error> {
+        self.steps += 1;
+
+        if self.max_steps.is_some_and(|limit| self.steps > limit) {
+            return Err(Error::Runtime(EXECUTION_LIMIT_EXCEEDED.to_string()));
+        }
+
         match statement {
-            Statement::Print(expression) => {
-                let value = format!("{}", self.evaluate(expression)?);
+            Statement::Print(expression, fallback) => {
+                let value = match (self.evaluate(expression)?, fallback) {
+                    (Value::None, Some(fallback)) => self.evaluate(fallback)?,
+                    (value, _) => value,
+                };
+                let value = format!("{}", value);
                 (self.print)(value);
+
+                if let Some(error) = self.write_error.borrow_mut().take() {
+                    return Err(Error::Runtime(error));
+                }
             },
             Statement::Expression(expression) => {
                 self.evaluate(expression)?;
             },
             Statement::Variable(name, expression) => {
-                if expression.is_some() {
-                    let value = self.evaluate(expression.as_ref().unwrap())?;
+                if let Some(expression) = expression {
+                    // The name exists while its initializer runs, so the initializer can't see an outer variable of the same name
+                    self.environment.borrow_mut().declare_uninitialized(name.to_string());
+                    let value = self.evaluate(expression)?;
                     self.environment.borrow_mut().declare(name.to_string(), value);
+                } else if self.strict_uninitialized {
+                    self.environment.borrow_mut().declare_uninitialized(name.to_string());
                 } else {
                     self.environment.borrow_mut().declare(name.to_string(), Value::None);
                 }
             },
+            Statement::Global(name, expression) => {
+                let value = self.evaluate(expression)?;
+                self.environment.borrow_mut().declare_global(name.to_string(), value);
+            },
             Statement::Block(statements) => {
                 let previous = Rc::clone(&self.environment);
                 self.environment = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(&self.environment))));
@@ -98,15 +258,25 @@ impl<F: FnMut(String)> Interpreter<F> {
                     }
                 }
             },
-            Statement::While(condition, body) => {
+            Statement::While(condition, body, else_body) => {
+                let mut completed = true;
+
                 while self.evaluate(condition)?.is_truthy() {
-                    let result = self.run_statement(body);
-                    if result.is_err() {
-                        return Err(result.err().unwrap())
+                    match self.run_statement(body) {
+                        Err(Error::Break) => {
+                            completed = false;
+                            break;
+                        },
+                        Err(error) => return Err(error),
+                        Ok(()) => {},
                     }
                 }
+
+                if let (true, Some(else_body)) = (completed, else_body) {
+                    self.run_statement(else_body)?;
+                }
             },
-            Statement::For(initial, condition, incrementer, body) => {
+            Statement::For(initial, condition, incrementer, body, else_body) => {
                 if let Some(initial) = initial {
                     let result = self.run_statement(initial);
                     if result.is_err() {
@@ -114,6 +284,8 @@ impl<F: FnMut(String)> Interpreter<F> {
                     }
                 }
 
+                let mut completed = true;
+
                 while {
                     if let Some(condition) = condition {
                         self.evaluate(condition)?.is_truthy()
@@ -121,23 +293,31 @@ impl<F: FnMut(String)> Interpreter<F> {
                         true
                     }
                 } {
-                    let result = self.run_statement(body);
-
-                    if result.is_err() {
-                        return Err(result.err().unwrap())
+                    match self.run_statement(body) {
+                        Err(Error::Break) => {
+                            completed = false;
+                            break;
+                        },
+                        Err(error) => return Err(error),
+                        Ok(()) => {},
                     }
 
                     if let Some(incrementer) = incrementer {
                         self.evaluate(incrementer)?;
                     }
                 }
+
+                if let (true, Some(else_body)) = (completed, else_body) {
+                    self.run_statement(else_body)?;
+                }
             },
+            Statement::Break => return Err(Error::Break),
             Statement::Function(name, parameters, body) => {
                 self.environment.borrow_mut().declare(name.clone(), Value::Callable(
                     Callable::Function(name.clone(), self.environment.clone(), parameters.clone(), body.clone())
                 ));
             },
-            Statement::Return(value) => {
+            Statement::Return(value, _) => {
                 return Err(Error::Return(match value {
                     Some(value) => self.evaluate(value)?,
                     None => Value::None
@@ -150,152 +330,282 @@ impl<F: FnMut(String)> Interpreter<F> {
 
     fn evaluate(&mut self, expression: &Expression) -> Result<Value, Error> {
         match expression {
-            Expression::Assign(name, expression) => {
+            Expression::Assign(name, expression, _) => {
                 let result = self.evaluate(expression)?;
                 self.environment.borrow_mut().assign(name.clone(), result.clone())?;
                 Ok(result)
             },
-            Expression::Literal(literal) => Ok(Value::from_literal(literal.clone())),
-            Expression::Grouping(expression) => self.evaluate(expression),
-            Expression::Unary(operation, expression) => {
+            Expression::Increment(name, operation, prefix, _) => self.evaluate_increment(name, operation, *prefix),
+            Expression::Literal(literal, _) => Ok(Value::from_literal(literal.clone())),
+            Expression::Grouping(expression, _) => self.evaluate(expression),
+            Expression::Unary(operation, expression, _) => {
                 match operation {
                     UnaryOperation::Minus => match self.evaluate(expression)? {
                         Value::Number(number) => Ok(Value::Number(-number)),
-                        _ => Err(Error::Runtime("Operand must be a number.".to_string())),
+                        value => Err(Error::Runtime(format!("Operand must be a number, got {}.", value.type_name()))),
                     },
                     UnaryOperation::Not => Ok(Value::Bool(!self.evaluate(expression)?.is_truthy())),
+                    UnaryOperation::AssertNonNil => match self.evaluate(expression)? {
+                        Value::None => Err(Error::Runtime("Unexpected nil value.".to_string())),
+                        value => Ok(value),
+                    },
                 }
             },
-            Expression::Binary(operation, left, right) => {
+            Expression::Binary(BinaryOperation::Plus, _, _, _) => self.evaluate_sum(expression),
+            Expression::Binary(operation, left, right, _) => {
                 let left = self.evaluate(left)?;
                 let right = self.evaluate(right)?;
 
-                Ok(match operation {
+                let value = match operation {
                     BinaryOperation::Equal => Value::Bool(left.is_equal(&right)),
                     BinaryOperation::NotEqual => Value::Bool(!left.is_equal(&right)),
+                    BinaryOperation::Plus => unreachable!("Sums are evaluated by evaluate_sum"),
                     operation => match (left, right) {
                         (Value::Number(left), Value::Number(right)) => match operation {
                             BinaryOperation::Multiply => Value::Number(left * right),
                             BinaryOperation::Divide => Value::Number(left / right),
-                            BinaryOperation::Plus => Value::Number(left + right),
                             BinaryOperation::Minus => Value::Number(left - right),
                             BinaryOperation::Greater => Value::Bool(left > right),
                             BinaryOperation::GreaterEqual => Value::Bool(left >= right),
                             BinaryOperation::Less => Value::Bool(left < right),
                             _ => Value::Bool(left <= right), // Last one can only be LessEqual
                         },
-                        (Value::String(left), Value::String(right)) => match operation {
-                            BinaryOperation::Plus => Value::String(format!("{}{}", left, right)),
-                            _ => return Err(Error::Runtime("Operands must be a numbers.".to_string())),
-                        }
-                        (_, _) => return Err(Error::Runtime("Operands must be a numbers.".to_string())),
-                    }
-                })
-            },
-            Expression::Variable(name) => {
-                if let Ok(value) = self.environment.borrow().get(name) {
-                    match value {
-                        Value::Bool(boolean) => Ok(Value::Bool(boolean)),
-                        Value::Number(number) => Ok(Value::Number(number)),
-                        Value::String(string) => Ok(Value::String(string.clone())),
-                        Value::Callable(callable) => Ok(Value::Callable(callable.clone())),
-                        Value::None => Ok(Value::None),
+                        (Value::Bool(left), Value::Bool(right)) if self.ordered_booleans && matches!(operation, BinaryOperation::Greater | BinaryOperation::GreaterEqual | BinaryOperation::Less | BinaryOperation::LessEqual) => match operation {
+                            BinaryOperation::Greater => Value::Bool(left & !right),
+                            BinaryOperation::GreaterEqual => Value::Bool(left | !right),
+                            BinaryOperation::Less => Value::Bool(!left & right),
+                            _ => Value::Bool(!left | right),
+                        },
+                        (left, right) => return Err(Error::Runtime(format!("Operands must be numbers, got {} and {}.", left.type_name(), right.type_name()))),
                     }
-                } else {
-                    Err(Error::Runtime(format!("Undefined variable '{}'.", name)))
-                }
+                };
+
+                self.check_nan(value)
             },
-            Expression::And(left, right) => {
+            Expression::Variable(name, _) => self.environment.borrow().get(name),
+            Expression::And(left, right, _) => {
                 let left = self.evaluate(left)?;
 
                 if !left.is_truthy() {
-                    return Ok(left);
+                    return Ok(self.logical_result(left));
                 }
 
-                self.evaluate(right)
+                let right = self.evaluate(right)?;
+                Ok(self.logical_result(right))
             },
-            Expression::Or(left, right) => {
+            Expression::Or(left, right, _) => {
                 let left = self.evaluate(left)?;
 
                 if left.is_truthy() {
-                    return Ok(left);
+                    return Ok(self.logical_result(left));
                 }
 
-                self.evaluate(right)
+                let right = self.evaluate(right)?;
+                Ok(self.logical_result(right))
             },
-            Expression::Call(callee, arguments) => {
+            Expression::Call(callee, arguments, line) => {
                 let callee = self.evaluate(callee)?;
 
-                match callee {
-                    Value::Callable(callable) => {
-                        match callable {
-                            Callable::Native(arity, function) => {
-                                if arguments.len() != arity {
-                                    return Err(Error::Runtime(format!("Expected {} arguments but got {}.", arity, arguments.len())));
-                                }
-
-                                let mut parameters: Vec<Value> = Vec::with_capacity(arguments.len());
-
-                                for argument in arguments {
-                                    parameters.push(self.evaluate(argument)?);
-                                }
-
-                                Ok(function(&parameters))
-                            }
-                            Callable::Function(_name, environment, parameters, body) => {
-                                if arguments.len() != parameters.len() {
-                                    return Err(Error::Runtime(format!("Expected {} arguments but got {}.", parameters.len(), arguments.len())));
-                                }
-
-                                let previous = Rc::clone(&self.environment);
-                                let function = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(&environment))));
-
-                                for index in 0..parameters.len() {
-                                    let value = self.evaluate(&arguments[index]);
-
-                                    if let Ok(value) = value {
-                                        function.borrow_mut().declare(parameters[index].clone(), value);
-                                    } else {
-                                        return Err(value.err().unwrap());
-                                    }
-                                }
-
-                                self.environment = function;
-
-                                let result = match *body {
-                                    Statement::Block(statements) => {
-                                        self.run_statements(&statements)
-                                    },
-                                    _ => Err(Error::Runtime("Expecting block statement".to_string()))
-                                };
-
-                                self.environment = previous;
-
-                                if result.is_err() {
-                                    match result.err().unwrap() {
-                                        Error::Return(value) => Ok(value),
-                                        Error::Runtime(value) => Err(Error::Runtime(value)),
-                                    }
-                                } else {
-                                    Ok(Value::None)
-                                }
-                            }
+                // A wrong argument count is known before the arguments run, so skip their side effects
+                if let Value::Callable(callable) = &callee {
+                    check_arity(callable, arguments.len())?;
+                }
+
+                let mut values: Vec<Value> = Vec::with_capacity(arguments.len());
+
+                for argument in arguments {
+                    values.push(self.evaluate(argument)?);
+                }
+
+                self.call(callee, values, Some(*line))
+            },
+            Expression::Interpolation(parts, _) => {
+                let mut string = String::new();
+
+                for part in parts {
+                    string.push_str(&self.evaluate(part)?.to_string());
+                }
+
+                Ok(Value::String(string))
+            },
+            Expression::Function(parameters, body, _) => Ok(Value::Callable(
+                Callable::Function("anonymous".to_string(), self.environment.clone(), parameters.clone(), body.clone())
+            )),
+        }
+    }
+
+    // Kept out of `evaluate` so its locals don't grow the frame every nested call goes through
+    fn evaluate_increment(&mut self, name: &str, operation: &BinaryOperation, prefix: bool) -> Result<Value, Error> {
+        let old = self.environment.borrow().get(name)?;
+        let new = match (&old, operation) {
+            (Value::Number(number), BinaryOperation::Plus) => Value::Number(number + 1.0),
+            (Value::Number(number), _) => Value::Number(number - 1.0),
+            (value, _) => return Err(Error::Runtime(format!("Operand must be a number, got {}.", value.type_name()))),
+        };
+
+        self.environment.borrow_mut().assign(name.to_string(), new.clone())?;
+        Ok(if prefix { new } else { old })
+    }
+
+    // Evaluates a left-associated chain of `+` operand by operand, a chain of strings is joined into one buffer
+    fn evaluate_sum(&mut self, expression: &Expression) -> Result<Value, Error> {
+        let mut operands: Vec<&Expression> = Vec::new();
+        let mut current = expression;
+
+        while let Expression::Binary(BinaryOperation::Plus, left, right, _) = current {
+            operands.push(right);
+            current = left;
+        }
+
+        let mut operands = operands.into_iter().rev();
+
+        let first = match self.evaluate(current)? {
+            Value::String(first) => {
+                let mut parts = vec![first];
+                let mut other = None;
+
+                for operand in operands.by_ref() {
+                    match self.evaluate(operand)? {
+                        Value::String(part) => parts.push(part),
+                        value => {
+                            other = Some(value);
+                            break;
+                        },
+                    }
+                }
+
+                let mut string = String::with_capacity(parts.iter().map(String::len).sum());
+                for part in parts {
+                    string.push_str(&part);
+                }
+
+                // A non-string operand falls back to adding pair by pair from where the strings stopped
+                match other {
+                    Some(value) => self.add(Value::String(string), value)?,
+                    None => return Ok(Value::String(string)),
+                }
+            },
+            first => first,
+        };
+
+        operands.try_fold(first, |left, operand| {
+            let right = self.evaluate(operand)?;
+            self.add(left, right)
+        })
+    }
+
+    fn add(&self, left: Value, right: Value) -> Result<Value, Error> {
+        match (left, right) {
+            (Value::Number(left), Value::Number(right)) => self.check_nan(Value::Number(left + right)),
+            (Value::String(left), Value::String(right)) => Ok(Value::String(format!("{}{}", left, right))),
+            (left, right) => Err(Error::Runtime(format!("Operands must be two numbers or two strings, got {} and {}.", left.type_name(), right.type_name()))),
+        }
+    }
+
+    fn logical_result(&self, value: Value) -> Value {
+        match self.strict_boolean_logic {
+            true => Value::Bool(value.is_truthy()),
+            false => value,
+        }
+    }
+
+    fn check_nan(&self, value: Value) -> Result<Value, Error> {
+        match value {
+            Value::Number(number) if self.strict_nan && number.is_nan() => Err(Error::Runtime("Operation produced NaN.".to_string())),
+            value => Ok(value),
+        }
+    }
+
+    fn call(&mut self, callee: Value, arguments: Vec<Value>, line: Option<usize>) -> Result<Value, Error> {
+        match callee {
+            Value::Callable(callable) => {
+                check_arity(&callable, arguments.len())?;
+
+                match callable {
+                    Callable::Native(_, function) | Callable::Variadic(_, function) => {
+                        function(&mut self.natives, &arguments).map_err(|error| Error::Runtime(match line {
+                            Some(line) => format!("[line {}] {}", line, error),
+                            None => error,
+                        }))
+                    }
+                    Callable::Composed(outer, inner) => {
+                        let value = self.call(*inner, arguments, line)?;
+                        self.call(*outer, vec![value], line)
+                    }
+                    Callable::Function(_name, environment, parameters, body) => {
+                        if self.recursion_limit.is_some_and(|limit| self.depth >= limit) {
+                            return Err(Error::Runtime("Stack overflow.".to_string()));
+                        }
+
+                        let previous = Rc::clone(&self.environment);
+                        let function = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(&environment))));
+
+                        for (parameter, value) in parameters.into_iter().zip(arguments) {
+                            function.borrow_mut().declare(parameter, value);
+                        }
+
+                        self.environment = function;
+                        self.depth += 1;
+
+                        let result = match *body {
+                            Statement::Block(statements) => {
+                                self.run_statements(&statements)
+                            },
+                            _ => Err(Error::Runtime("Expecting block statement".to_string()))
+                        };
+
+                        self.depth -= 1;
+                        self.environment = previous;
+
+                        match result {
+                            Ok(()) => Ok(Value::None),
+                            Err(Error::Return(value)) => Ok(value),
+                            Err(error) => Err(error),
                         }
                     }
-                    _ => Err(Error::Runtime("Can only call functions and classes.".to_string()))
                 }
             }
+            value => Err(Error::Runtime(format!("Can only call functions and classes, got {}.", value.type_name())))
+        }
+    }
+}
+
+#[derive(Default)]
+struct TopLevelReturn {
+    line: Option<usize>,
+}
+
+impl ExpressionVisitor for TopLevelReturn {}
+
+impl StatementVisitor for TopLevelReturn {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Function(_, _, _) => {},
+            Statement::Return(_, line) => {
+                self.line.get_or_insert(*line);
+            },
+            statement => walk_statement(self, statement),
         }
     }
 }
 
+fn find_top_level_return(statements: &[Statement]) -> Option<usize> {
+    let mut visitor = TopLevelReturn::default();
+
+    for statement in statements {
+        visitor.visit_statement(statement);
+    }
+
+    visitor.line
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::*;
     use std::time::Duration;
     use lox_syntax::parser::Parser;
     use lox_syntax::tokenizer::Scanner;
-    use crate::interpreter::Interpreter;
+    use crate::interpreter::{Interpreter, InterpreterBuilder, EXECUTION_LIMIT_EXCEEDED};
     use crate::value::Value;
 
     fn run_evaluate(source: &str) -> Result<Value, String> {
@@ -303,21 +613,196 @@ mod tests {
         let (tokens, _) = scanner.scan_tokens();
         let mut parser = Parser::new(tokens);
         let mut interpreter = Interpreter::new(|_|{});
-        interpreter.evaluate_expression(&parser.parse_expression()?)
+        interpreter.evaluate_expression(&parser.parse_expression().map_err(|error| error.to_string())?)
     }
 
     fn run_statement(source: &str) -> Result<Vec<String>, String> {
+        run_statement_with(InterpreterBuilder::new(), source)
+    }
+
+    fn run_statement_with(builder: InterpreterBuilder, source: &str) -> Result<Vec<String>, String> {
         let mut scanner = Scanner::new(source);
         let (tokens, _) = scanner.scan_tokens();
         let mut parser = Parser::new(tokens);
         let mut prints: Vec<String> = Vec::new();
-        let mut interpreter = Interpreter::new(|value|{
+        let mut interpreter = builder.build(|value|{
             prints.push(value);
         });
-        interpreter.run(&parser.parse()?)?;
+        interpreter.run(&parser.parse().map_err(|error| error.to_string())?)?;
         Ok(prints)
     }
 
+    #[rstest]
+    #[case("fun main() { print \"main\"; } print \"top\";", Ok(vec!["top", "main"]))]
+    #[case("var main = fun () { print 1; };", Ok(vec!["1"]))]
+    #[case("fun helper() {}", Err("No 'main' function defined."))]
+    #[case("var main = 1;", Err("No 'main' function defined."))]
+    #[case("fun main(a) {}", Err("Expected 1 arguments but got 0."))]
+    fn test_call_main(#[case] input: &str, #[case] expected: Result<Vec<&str>, &str>) {
+        let mut scanner = Scanner::new(input);
+        let (tokens, _) = scanner.scan_tokens();
+        let (mut interpreter, output) = InterpreterBuilder::new().collecting();
+        interpreter.run(&Parser::new(tokens).parse().unwrap()).unwrap();
+
+        match expected {
+            Ok(prints) => {
+                interpreter.call_main().unwrap();
+                assert_eq!(prints, *output.borrow());
+            },
+            Err(error) => assert_eq!(error, interpreter.call_main().err().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_builder_collecting() {
+        let mut scanner = Scanner::new("print 1; var a = \"two\"; print a; print nil;");
+        let (tokens, _) = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        let (mut interpreter, output) = InterpreterBuilder::new().collecting();
+
+        interpreter.run(&parser.parse().unwrap()).unwrap();
+        assert_eq!(vec!["1", "two", "nil"], *output.borrow());
+
+        output.borrow_mut().clear();
+        let mut scanner = Scanner::new("print a + \"!\";");
+        let (tokens, _) = scanner.scan_tokens();
+        interpreter.run(&Parser::new(tokens).parse().unwrap()).unwrap();
+        assert_eq!(vec!["two!"], *output.borrow());
+    }
+
+    #[test]
+    fn test_run_twice_with_reset_globals() {
+        let mut scanner = Scanner::new("var count = 0; fun bump() { count = count + 1; return count; } print bump(); print bump(); global total = count;");
+        let (tokens, _) = scanner.scan_tokens();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let (mut interpreter, output) = InterpreterBuilder::new().collecting();
+
+        interpreter.run(&statements).unwrap();
+        let first = output.borrow_mut().split_off(0);
+        interpreter.reset_globals();
+        interpreter.run(&statements).unwrap();
+
+        assert_eq!(vec!["1", "2"], first);
+        assert_eq!(first, *output.borrow());
+    }
+
+    #[test]
+    fn test_reset_globals_keeps_natives() {
+        let mut scanner = Scanner::new("var a = 1; print clock() > 0;");
+        let (tokens, _) = scanner.scan_tokens();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let (mut interpreter, output) = InterpreterBuilder::new().collecting();
+
+        interpreter.run(&statements).unwrap();
+        interpreter.reset_globals();
+
+        let mut scanner = Scanner::new("print a;");
+        let (tokens, _) = scanner.scan_tokens();
+        assert_eq!("Undefined variable 'a'.", interpreter.run(&Parser::new(tokens).parse().unwrap()).err().unwrap());
+
+        interpreter.run(&statements).unwrap();
+        assert_eq!(vec!["true", "true"], *output.borrow());
+    }
+
+    #[test]
+    fn test_evaluate_source() {
+        let mut scanner = Scanner::new("var count = 2; fun double(x) { return x * 2; }");
+        let (tokens, _) = scanner.scan_tokens();
+        let (mut interpreter, _output) = InterpreterBuilder::new().collecting();
+        interpreter.run(&Parser::new(tokens).parse().unwrap()).unwrap();
+
+        assert_eq!(Value::Number(4.0), interpreter.evaluate_source("double(count)").unwrap());
+        assert_eq!(Value::Number(3.0), interpreter.evaluate_source("count = count + 1;").unwrap());
+        assert_eq!(Value::Number(3.0), interpreter.evaluate_source("count").unwrap());
+    }
+
+    #[rstest]
+    #[case("missing", "Undefined variable 'missing'.")]
+    #[case("1 +", "[line 1] Error at end: Expect expression.")]
+    #[case("@", "[line 1] Error: Unexpected character: @")]
+    #[case("print 1;", "Expect a single expression.")]
+    #[case("1; 2;", "Expect a single expression.")]
+    fn test_evaluate_source_error(#[case] source: &str, #[case] expected: &str) {
+        let (mut interpreter, _output) = InterpreterBuilder::new().collecting();
+
+        assert_eq!(expected, interpreter.evaluate_source(source).err().unwrap());
+    }
+
+    #[test]
+    fn test_builder_writer() {
+        let mut scanner = Scanner::new("print 1; print \"two\"; print nil;");
+        let (tokens, _) = scanner.scan_tokens();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let mut bytes: Vec<u8> = Vec::new();
+
+        InterpreterBuilder::new().writer(&mut bytes).run(&statements).unwrap();
+        assert_eq!(b"1\ntwo\nnil\n".to_vec(), bytes);
+    }
+
+    struct FailingWriter;
+
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "pipe closed"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_builder_writer_error() {
+        let mut scanner = Scanner::new("print 1; print 2;");
+        let (tokens, _) = scanner.scan_tokens();
+        let statements = Parser::new(tokens).parse().unwrap();
+
+        assert_eq!("Failed to write output: pipe closed", InterpreterBuilder::new().writer(FailingWriter).run(&statements).err().unwrap());
+    }
+
+    #[test]
+    fn test_builder_options_with_output() {
+        let mut scanner = Scanner::new("print 1; print 2; print 3;");
+        let (tokens, _) = scanner.scan_tokens();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let mut bytes: Vec<u8> = Vec::new();
+
+        assert_eq!(EXECUTION_LIMIT_EXCEEDED, InterpreterBuilder::new().max_steps(2).writer(&mut bytes).run(&statements).err().unwrap());
+        assert_eq!(b"1\n2\n".to_vec(), bytes);
+
+        let (mut interpreter, output) = InterpreterBuilder::new().max_steps(1).collecting();
+        assert_eq!(EXECUTION_LIMIT_EXCEEDED, interpreter.run(&statements).err().unwrap());
+        assert_eq!(vec!["1"], *output.borrow());
+    }
+
+    fn map<F: FnMut(String)>(interpreter: &mut Interpreter<F>, function: &Value, values: Vec<Value>) -> Result<Vec<Value>, String> {
+        values.into_iter().map(|value| interpreter.call_value(function.clone(), vec![value])).collect()
+    }
+
+    #[test]
+    fn test_call_value_map() {
+        let mut scanner = Scanner::new("var offset = 1; fun scale(x) { print x; return x * 2 + offset; }");
+        let (tokens, _) = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        let mut prints: Vec<String> = Vec::new();
+        let mut interpreter = Interpreter::new(|value| prints.push(value));
+        interpreter.run(&parser.parse().unwrap()).unwrap();
+
+        let mut scanner = Scanner::new("scale");
+        let (tokens, _) = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        let function = interpreter.evaluate_expression(&parser.parse_expression().unwrap()).unwrap();
+
+        let result = map(&mut interpreter, &function, vec![Value::Number(1.0), Value::Number(2.0), Value::Number(3.0)]);
+        assert_eq!(Ok(vec![Value::Number(3.0), Value::Number(5.0), Value::Number(7.0)]), result);
+
+        let error = map(&mut interpreter, &function, vec![Value::String("a".to_string())]);
+        assert_eq!(Err("Operands must be numbers, got string and number.".to_string()), error);
+
+        drop(interpreter);
+        assert_eq!(vec!["1", "2", "3", "a"], prints);
+    }
+
     #[rstest]
     #[case("true", "true")]
     #[case("false", "false")]
@@ -390,10 +875,35 @@ mod tests {
     #[case("\"hello\" + \" world!\"", "hello world!")]
     #[case("\"foo\" + \"bar\"", "foobar")]
     #[case("\"42\" + \"24\"", "4224")]
+    #[case("\"a\" + \"b\" + \"c\" + \"d\" + \"e\"", "abcde")]
+    #[case("\"a\" + (\"b\" + \"c\") + \"\" + \"d\"", "abcd")]
+    #[case("1 + 2 + 3 + 4", "10")]
+    #[case("1 + 2 * 3 + 4", "11")]
     fn test_evaluate_string_concatenation(#[case] input: &str, #[case] expected: &str) {
         assert_eq!(expected, run_evaluate(input).unwrap().to_string());
     }
 
+    #[rstest]
+    #[case("\"a\" + \"b\" + 1 + \"c\"", "Operands must be two numbers or two strings, got string and number.")]
+    #[case("1 + 2 + \"c\" + \"d\"", "Operands must be two numbers or two strings, got number and string.")]
+    #[case("nil + \"a\" + \"b\"", "Operands must be two numbers or two strings, got nil and string.")]
+    #[case("\"a\" + \"b\" + \"c\" + true", "Operands must be two numbers or two strings, got string and bool.")]
+    fn test_evaluate_string_concatenation_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_evaluate(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("var calls = 0; fun f(s) { calls = calls + 1; return s; } print f(\"a\") + f(1) + f(\"b\");", "Operands must be two numbers or two strings, got string and number.", "2")]
+    #[case("var calls = 0; fun f(s) { calls = calls + 1; return s; } print f(1) + f(\"a\") + f(\"b\");", "Operands must be two numbers or two strings, got number and string.", "2")]
+    fn test_statements_concatenation_stops_at_error(#[case] input: &str, #[case] expected: &str, #[case] calls: &str) {
+        let mut scanner = Scanner::new(input);
+        let (tokens, _) = scanner.scan_tokens();
+        let mut interpreter = Interpreter::new(|_|{});
+
+        assert_eq!(expected, interpreter.run(&Parser::new(tokens).parse().unwrap()).err().unwrap());
+        assert_eq!(calls, interpreter.evaluate_source("calls").unwrap().to_string());
+    }
+
     #[rstest]
     #[case("57 > -65", "true")]
     #[case("57 > 65", "false")]
@@ -407,6 +917,9 @@ mod tests {
     #[case("11 <= 11", "true")]
     #[case("12 <= 11", "false")]
     #[case("10 <= 11", "true")]
+    #[case("5 > 4.5", "true")]
+    #[case("5 <= 5.0", "true")]
+    #[case("5 == 5.0", "true")]
     fn test_evaluate_relational(#[case] input: &str, #[case] expected: &str) {
         assert_eq!(expected, run_evaluate(input).unwrap().to_string());
     }
@@ -446,23 +959,32 @@ mod tests {
     }
 
     #[rstest]
-    #[case("-\"foo\"", "Operand must be a number.")]
-    #[case("-false", "Operand must be a number.")]
-    #[case("-nil", "Operand must be a number.")]
-    #[case("\"foo\" * 42", "Operands must be a numbers.")]
-    #[case("(\"foo\" * \"bar\")", "Operands must be a numbers.")]
-    #[case("true / 2", "Operands must be a numbers.")]
-    #[case("true / false", "Operands must be a numbers.")]
-    #[case("\"foo\" + true", "Operands must be a numbers.")]
-    #[case("42 - true", "Operands must be a numbers.")]
-    #[case("true + false", "Operands must be a numbers.")]
-    #[case("\"foo\" - \"bar\"", "Operands must be a numbers.")]
-    #[case("\"foo\" < false", "Operands must be a numbers.")]
-    #[case("true < 2", "Operands must be a numbers.")]
-    #[case("(\"foo\" + \"bar\") < 42", "Operands must be a numbers.")]
-    #[case("false > true", "Operands must be a numbers.")]
-    #[case("\"foo\" <= false", "Operands must be a numbers.")]
-    #[case("\"foo\" >= false", "Operands must be a numbers.")]
+    #[case("-\"foo\"", "Operand must be a number, got string.")]
+    #[case("-false", "Operand must be a number, got bool.")]
+    #[case("-nil", "Operand must be a number, got nil.")]
+    #[case("-clock", "Operand must be a number, got function.")]
+    #[case("\"foo\"()", "Can only call functions and classes, got string.")]
+    #[case("nil()", "Can only call functions and classes, got nil.")]
+    #[case("1(2)", "Can only call functions and classes, got number.")]
+    #[case("\"foo\" * 42", "Operands must be numbers, got string and number.")]
+    #[case("(\"foo\" * \"bar\")", "Operands must be numbers, got string and string.")]
+    #[case("true / 2", "Operands must be numbers, got bool and number.")]
+    #[case("true / false", "Operands must be numbers, got bool and bool.")]
+    #[case("\"foo\" + true", "Operands must be two numbers or two strings, got string and bool.")]
+    #[case("42 - true", "Operands must be numbers, got number and bool.")]
+    #[case("true + false", "Operands must be two numbers or two strings, got bool and bool.")]
+    #[case("\"foo\" - \"bar\"", "Operands must be numbers, got string and string.")]
+    #[case("\"foo\" < false", "Operands must be numbers, got string and bool.")]
+    #[case("true < 2", "Operands must be numbers, got bool and number.")]
+    #[case("(\"foo\" + \"bar\") < 42", "Operands must be numbers, got string and number.")]
+    #[case("false > true", "Operands must be numbers, got bool and bool.")]
+    #[case("\"foo\" <= false", "Operands must be numbers, got string and bool.")]
+    #[case("\"foo\" >= false", "Operands must be numbers, got string and bool.")]
+    #[case("nil < nil", "Operands must be numbers, got nil and nil.")]
+    #[case("nil > 1", "Operands must be numbers, got nil and number.")]
+    #[case("true <= nil", "Operands must be numbers, got bool and nil.")]
+    #[case("clock >= 1", "Operands must be numbers, got function and number.")]
+    #[case("nil + 1", "Operands must be two numbers or two strings, got nil and number.")]
     fn test_evaluate_runtime_error(#[case] input: &str, #[case] expected: &str) {
         assert_eq!(expected, run_evaluate(input).err().unwrap());
     }
@@ -484,6 +1006,44 @@ mod tests {
         assert_eq!(expected, run_statement(input).unwrap());
     }
 
+    #[rstest]
+    #[case("var a; a = nil; print a else \"default\";", vec!["default"])]
+    #[case("var a = 1; print a else \"default\";", vec!["1"])]
+    #[case("print false else \"default\";", vec!["false"])]
+    #[case("fun f() {} print f() else \"none\";", vec!["none"])]
+    #[case("print 1 else undefined;", vec!["1"])]
+    #[case("print nil else nil;", vec!["nil"])]
+    fn test_statements_print_else(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+
+    #[rstest]
+    #[case(false, "var x; print x;", vec!["nil"])]
+    #[case(false, "var x; fun show() { print x; } show();", vec!["nil"])]
+    #[case(true, "var x = nil; print x;", vec!["nil"])]
+    #[case(true, "var x; x = nil; print x;", vec!["nil"])]
+    #[case(true, "var x; x = 1; print x;", vec!["1"])]
+    #[case(true, "var x; { x = 2; } print x;", vec!["2"])]
+    fn test_statements_uninitialized_variable(#[case] enabled: bool, #[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement_with(InterpreterBuilder::new().strict_uninitialized(enabled), input).unwrap());
+    }
+
+    #[rstest]
+    #[case("var x; print x;", "Variable 'x' used before assignment.")]
+    #[case("var x; { print x; }", "Variable 'x' used before assignment.")]
+    #[case("var x; fun show() { print x; } show();", "Variable 'x' used before assignment.")]
+    fn test_statements_uninitialized_variable_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement_with(InterpreterBuilder::new().strict_uninitialized(true), input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("var x = 1; { var x = 2; { global x = 3; print x; } } print x;", vec!["2", "3"])]
+    #[case("{ { global y = \"new\"; } } print y;", vec!["new"])]
+    #[case("var x = 1; fun set() { var x = 5; global x = x + 1; print x; } set(); print x;", vec!["5", "6"])]
+    fn test_statements_global(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
 
     #[rstest]
     #[case("print \"hi\" or 2;", vec!["hi"])]
@@ -497,6 +1057,10 @@ mod tests {
     #[case("print true and false;", vec!["false"])]
     #[case("print false and true;", vec!["false"])]
     #[case("print \"quz\" or \"quz\";", vec!["quz"])]
+    #[case("print 1 == 1 or 2 == 3;", vec!["true"])]
+    #[case("print 1 == 2 or 2 == 3;", vec!["false"])]
+    #[case("print 1 == 1 and 2 == 3;", vec!["false"])]
+    #[case("print false and true or true;", vec!["true"])]
     #[case("if (\"hi\" or 2) { print \"yes\"; }", vec!["yes"])]
     #[case("if (false) {  } else { print \"yes\"; }", vec!["yes"])]
     #[case("if (false) {  }", vec![])]
@@ -504,6 +1068,36 @@ mod tests {
         assert_eq!(expected, run_statement(input).unwrap());
     }
 
+    #[rstest]
+    #[case("print \"sum = ${1+2}\";", vec!["sum = 3"])]
+    #[case("print \"\\${1+2}\";", vec!["${1+2}"])]
+    #[case("var name = \"lox\"; print \"hi ${name}, ${nil} ${true} ${1.5}\";", vec!["hi lox, nil true 1.5"])]
+    #[case("fun f(x) { return x * 2; } print \"${f(2)}${\"-${f(3)}\"}\";", vec!["4-6"])]
+    fn test_statements_interpolation(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("var i = 1; print i++; print i;", vec!["1", "2"])]
+    #[case("var i = 1; print ++i; print i;", vec!["2", "2"])]
+    #[case("var i = 1; print i--; print i;", vec!["1", "0"])]
+    #[case("var i = 1; print --i; print i;", vec!["0", "0"])]
+    #[case("for (var i = 0; i < 3; i++) print i;", vec!["0", "1", "2"])]
+    #[case("var i = 0.1; print i++; print i;", vec!["0.1", "1.1"])]
+    #[case("var i = 0.1; print i--; print --i;", vec!["0.1", "-1.9"])]
+    #[case("var x = 2; print --x; print -(-x);", vec!["1", "1"])]
+    fn test_statements_increment_decrement(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("var s = \"a\"; s++;", "Operand must be a number, got string.")]
+    #[case("var n = nil; ++n;", "Operand must be a number, got nil.")]
+    #[case("++missing;", "Undefined variable 'missing'.")]
+    fn test_statements_increment_decrement_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
     #[rstest]
     #[case("if (a) { print \"yes\"; }", "Undefined variable 'a'.")]
     #[case("if (1) { print a; }", "Undefined variable 'a'.")]
@@ -515,6 +1109,9 @@ mod tests {
 
     #[rstest]
     #[case("var i = 0; while(i < 5) {i = i + 1; print \"hi\"; }", vec!["hi", "hi", "hi", "hi", "hi"])]
+    #[case("var i = 0; while ((i = i + 1) <= 3) print i; print i;", vec!["1", "2", "3", "4"])]
+    #[case("var n = 0; fun next() { n = n + 1; if (n > 2) return nil; return n; } var line; while ((line = next()) != nil) print line; print line;", vec!["1", "2", "nil"])]
+    #[case("var i = 0; { var i = 10; while ((i = i - 1) > 8) print i; } print i;", vec!["9", "0"])]
     fn test_statements_while(#[case] input: &str, #[case] expected: Vec<&str>) {
         assert_eq!(expected, run_statement(input).unwrap());
     }
@@ -530,10 +1127,25 @@ mod tests {
     #[rstest]
     #[case("for (var baz = 0; baz < 3;) print baz = baz + 1;", vec!["1", "2", "3"])]
     #[case("for (var world = 0; world < 3; world = world + 1) { print world; }", vec!["0", "1", "2"])]
+    #[case("var count = 0; for (; (count = count + 1) < 3;) print count; print count;", vec!["1", "2", "3"])]
     fn test_statements_for(#[case] input: &str, #[case] expected: Vec<&str>) {
         assert_eq!(expected, run_statement(input).unwrap());
     }
 
+    #[rstest]
+    #[case("var i = 0; while (i < 2) { print i; i = i + 1; } else { print \"done\"; }", vec!["0", "1", "done"])]
+    #[case("var i = 0; while (i < 5) { if (i == 1) break; print i; i = i + 1; } else { print \"done\"; }", vec!["0"])]
+    #[case("for (var i = 0; i < 2; i = i + 1) print i; else print \"not found\";", vec!["0", "1", "not found"])]
+    #[case("for (var i = 0; i < 5; i = i + 1) { if (i == 3) { print \"found\"; break; } } else print \"not found\";", vec!["found"])]
+    #[case("for (var i = 0; i < 5; i = i + 1) { if (i == 2) break; } print i;", vec!["2"])]
+    #[case("while (true) { while (true) break; print \"outer\"; break; }", vec!["outer"])]
+    #[case("fun find() { while (true) return 1; else print \"unreachable\"; } print find();", vec!["1"])]
+    #[case("if (false) while (false) print 1; else print 2;", vec!["2"])]
+    #[case("if (true) while (false) print 1; else print 2;", vec![])]
+    fn test_statements_loop_break_else(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
     #[rstest]
     #[case("for (;i < 5;) {i = i + 1; print \"hi\"; }", "Undefined variable 'i'.")]
     #[case("for (;;) { print a; }", "Undefined variable 'a'.")]
@@ -548,6 +1160,276 @@ mod tests {
         assert_eq!(expected, run_statement(input).unwrap());
     }
 
+    #[rstest]
+    #[case("print approx_equal(0.1 + 0.2, 0.3, 0.000001);", vec!["true"])]
+    #[case("print approx_equal(0.1 + 0.2, 0.3, 0);", vec!["false"])]
+    #[case("print approx_equal(1, 1, 0);", vec!["true"])]
+    fn test_statements_native_approx_equal(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("approx_equal(\"1\", 1, 0);", "[line 1] Arguments must be numbers.")]
+    #[case("approx_equal(1, nil, 0);", "[line 1] Arguments must be numbers.")]
+    #[case("approx_equal(1, 1, true);", "[line 1] Arguments must be numbers.")]
+    #[case("approx_equal(1, 1);", "Expected 3 arguments but got 2.")]
+    fn test_statements_native_approx_equal_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("clock(1);", "Expected 0 arguments but got 1.")]
+    #[case("approx_equal(1, 2, 3, 4);", "Expected 3 arguments but got 4.")]
+    #[case("chr();", "Expected 1 arguments but got 0.")]
+    #[case("ord(\"a\", \"b\");", "Expected 1 arguments but got 2.")]
+    #[case("scientific(1);", "Expected 2 arguments but got 1.")]
+    #[case("expect(1);", "Expected 2 arguments but got 1.")]
+    #[case("compose(chr);", "Expected 2 arguments but got 1.")]
+    #[case("is_integer(1, 2);", "Expected 1 arguments but got 2.")]
+    #[case("bool();", "Expected 1 arguments but got 0.")]
+    fn test_statements_native_arity_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("var a = 0; fun bump() { a = a + 1; print a; return a; } clock(bump());")]
+    #[case("var a = 0; fun bump() { a = a + 1; print a; return a; } fun two(x, y) {} two(bump());")]
+    #[case("var a = 0; fun bump() { a = a + 1; print a; return a; } compose(chr, ord)(bump(), bump());")]
+    fn test_statements_arity_checked_before_arguments(#[case] input: &str) {
+        let (mut interpreter, output) = InterpreterBuilder::new().collecting();
+        let mut scanner = Scanner::new(input);
+        let (tokens, _) = scanner.scan_tokens();
+
+        assert!(interpreter.run(&Parser::new(tokens).parse().unwrap()).is_err());
+        assert!(output.borrow().is_empty());
+    }
+
+    #[rstest]
+    #[case("print ord(\"A\");", vec!["65"])]
+    #[case("print chr(65);", vec!["A"])]
+    #[case("print chr(ord(\"é\"));", vec!["é"])]
+    #[case("print ord(chr(8364));", vec!["8364"])]
+    fn test_statements_native_chr_ord(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("ord(\"AB\");", "[line 1] Argument must be a single character.")]
+    #[case("ord(\"\");", "[line 1] Argument must be a single character.")]
+    #[case("ord(65);", "[line 1] Argument must be a string.")]
+    #[case("chr(1114112);", "[line 1] Invalid code point 1114112.")]
+    #[case("chr(-1);", "[line 1] Invalid code point -1.")]
+    #[case("chr(65.5);", "[line 1] Invalid code point 65.5.")]
+    #[case("chr(\"A\");", "[line 1] Argument must be a number.")]
+    fn test_statements_native_chr_ord_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("print scientific(602200000000000000000000, 2);", vec!["6.02e23"])]
+    #[case("print scientific(0.000123, 1);", vec!["1.2e-4"])]
+    #[case("print scientific(-1500, 0);", vec!["-2e3"])]
+    #[case("print scientific(1, 3);", vec!["1.000e0"])]
+    fn test_statements_native_scientific(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("scientific(1, -1);", "[line 1] Digits must be a non-negative integer.")]
+    #[case("scientific(1, 1.5);", "[line 1] Digits must be a non-negative integer.")]
+    #[case("scientific(1, 0 / 0);", "[line 1] Digits must be a non-negative integer.")]
+    #[case("scientific(1, 1000000000000);", "[line 1] Digits must be at most 100.")]
+    #[case("scientific(1, 101);", "[line 1] Digits must be at most 100.")]
+    #[case("scientific(\"1\", 2);", "[line 1] Arguments must be numbers.")]
+    #[case("scientific(1, nil);", "[line 1] Arguments must be numbers.")]
+    fn test_statements_native_scientific_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("expect(1 + 2, 3); print \"ok\";", vec!["ok"])]
+    #[case("expect(\"a\" + \"b\", \"ab\"); expect(nil, nil); expect(!true, false);", vec![])]
+    #[case("print expect(1, 1);", vec!["nil"])]
+    fn test_statements_native_expect(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("expect(1 + 2, 4);", "[line 1] Expected 4 but got 3.")]
+    #[case("print 1;\nexpect(\"a\", \"b\");", "[line 2] Expected \"b\" but got \"a\".")]
+    #[case("expect(1, \"1\");", "[line 1] Expected \"1\" but got 1.")]
+    #[case("expect(\"1\", 1);", "[line 1] Expected 1 but got \"1\".")]
+    #[case("expect(\"nil\", nil);", "[line 1] Expected nil but got \"nil\".")]
+    #[case("expect(true, \"true\");", "[line 1] Expected \"true\" but got true.")]
+    #[case("expect(nil, false);", "[line 1] Expected false but got nil.")]
+    fn test_statements_native_expect_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("fun double(x) { return x * 2; } fun inc(x) { return x + 1; } var f = compose(double, inc); print f(3);", vec!["8"])]
+    #[case("fun double(x) { return x * 2; } fun inc(x) { return x + 1; } print compose(inc, double)(3);", vec!["7"])]
+    #[case("fun inc(x) { return x + 1; } print compose(compose(inc, inc), inc)(0);", vec!["3"])]
+    #[case("fun code(c) { return ord(c); } print compose(chr, code)(\"A\");", vec!["A"])]
+    #[case("fun id(x) { return x; } print compose(id, id);", vec!["<native fn>"])]
+    fn test_statements_native_compose(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("fun id(x) { return x; } compose(id, 1);", "[line 1] Arguments must be functions.")]
+    #[case("fun id(x) { return x; } compose(id, id)(1, 2);", "Expected 1 arguments but got 2.")]
+    #[case("fun two(a, b) { return a; } fun id(x) { return x; } compose(id, two)(1);", "Expected 2 arguments but got 1.")]
+    fn test_statements_native_compose_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("var x = \"global\"; fun f() { var x = \"f\"; while (true) { var x = \"loop\"; if (true) { var x = \"if\"; return x; } } } print f(); print x;", vec!["if", "global"])]
+    #[case("fun f(n) { for (var i = 0; i < 10; i = i + 1) { { if (i == n) { return i * 10; } } } return -1; } print f(3); print f(20);", vec!["30", "-1"])]
+    #[case("var a = \"outer\"; fun f() { var a = \"inner\"; { { { return a; } } } } { var a = \"block\"; print f(); print a; } print a;", vec!["inner", "block", "outer"])]
+    fn test_statements_nested_return(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("fun f() { { var hidden = 1; while (true) { if (true) { return 0; } } } } f(); print hidden;", "Undefined variable 'hidden'.")]
+    fn test_statements_nested_return_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("print is_integer(3);", vec!["true"])]
+    #[case("print is_integer(3.5);", vec!["false"])]
+    #[case("print is_integer(-2 * 1.5);", vec!["true"])]
+    #[case("print is_integer(1 / 0);", vec!["false"])]
+    fn test_statements_native_is_integer(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("is_integer(\"3\");", "[line 1] Argument must be a number.")]
+    #[case("is_integer(nil);", "[line 1] Argument must be a number.")]
+    fn test_statements_native_is_integer_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("print bool(0);", vec!["true"])]
+    #[case("print bool(nil);", vec!["false"])]
+    #[case("print bool(\"\");", vec!["true"])]
+    #[case("print bool(false);", vec!["false"])]
+    #[case("print bool(clock);", vec!["true"])]
+    #[case("print bool(!bool(1));", vec!["false"])]
+    fn test_statements_native_bool(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("print printf(\"%s=%d\", \"n\", 3);", vec!["n=3"])]
+    #[case("print printf(\"plain\");", vec!["plain"])]
+    #[case("print printf(\"%d%%\", 99.9);", vec!["99%"])]
+    #[case("print printf(\"%f\", 1.5);", vec!["1.500000"])]
+    #[case("print printf(\"%s %s %s\", nil, true, 2.5);", vec!["nil true 2.5"])]
+    #[case("print printf;", vec!["<native fn>"])]
+    fn test_statements_native_printf(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_statements_native_getenv() {
+        std::env::set_var("LOX_TEST_GETENV", "configured");
+        std::env::remove_var("LOX_TEST_GETENV_UNSET");
+
+        assert_eq!(vec!["configured", "nil"], run_statement("print getenv(\"LOX_TEST_GETENV\"); print getenv(\"LOX_TEST_GETENV_UNSET\");").unwrap());
+        assert_eq!("[line 1] Argument must be a string.", run_statement("getenv(1);").err().unwrap());
+    }
+
+    #[rstest]
+    #[case("fun f() {} print is_callable(f);", vec!["true"])]
+    #[case("print is_callable(clock);", vec!["true"])]
+    #[case("print is_callable(compose(chr, ord));", vec!["true"])]
+    #[case("print is_callable(1);", vec!["false"])]
+    #[case("print is_callable(\"clock\");", vec!["false"])]
+    #[case("print is_callable(nil);", vec!["false"])]
+    fn test_statements_native_is_callable(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[test]
+    fn test_statements_native_random_seeded() {
+        let source = "seed(42); for (var i = 0; i < 5; i = i + 1) print random();";
+        let first = run_statement(source).unwrap();
+
+        assert_eq!(first, run_statement(source).unwrap());
+        assert_ne!(first, run_statement("seed(43); for (var i = 0; i < 5; i = i + 1) print random();").unwrap());
+        assert!(first.iter().map(|value| value.parse::<f64>().unwrap()).all(|value| (0.0..1.0).contains(&value)));
+    }
+
+    #[test]
+    fn test_statements_native_random_per_interpreter() {
+        let parse = |source: &str| Parser::new(Scanner::new(source).scan_tokens().0).parse().unwrap();
+        let (mut first, first_output) = InterpreterBuilder::new().collecting();
+        let (mut second, second_output) = InterpreterBuilder::new().collecting();
+
+        first.run(&parse("seed(42);")).unwrap();
+        second.run(&parse("seed(42);")).unwrap();
+        first.run(&parse("print random();")).unwrap();
+        second.run(&parse("print random();")).unwrap();
+
+        assert_eq!(*first_output.borrow(), *second_output.borrow());
+    }
+
+    #[rstest]
+    #[case("seed(\"42\");", "[line 1] Seed must be an integer.")]
+    #[case("seed(1.5);", "[line 1] Seed must be an integer.")]
+    #[case("seed(nil);", "[line 1] Seed must be an integer.")]
+    fn test_statements_native_seed_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("var x = 1; print x!;", vec!["1"])]
+    #[case("var x = false; print x!;", vec!["false"])]
+    #[case("fun f() { return \"value\"; } print f()! + \"!\";", vec!["value!"])]
+    #[case("var x = 2; print !x!;", vec!["false"])]
+    #[case("var x = 2; print x! != nil;", vec!["true"])]
+    fn test_statements_assert_non_nil(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("var x = nil; print x!;")]
+    #[case("fun f() {} print f()!;")]
+    #[case("print nil!;")]
+    fn test_statements_assert_non_nil_error(#[case] input: &str) {
+        assert_eq!("Unexpected nil value.", run_statement(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("printf();", "Expected at least 1 arguments but got 0.")]
+    #[case("printf(\"%s=%d\", \"n\");", "[line 1] Not enough arguments for format string.")]
+    #[case("printf(\"%s\", 1, 2);", "[line 1] Too many arguments for format string.")]
+    #[case("printf(\"%d\", \"3\");", "[line 1] Format '%d' expects a number, got string.")]
+    #[case("printf(\"%x\", 1);", "[line 1] Unknown format specifier '%x'.")]
+    #[case("printf(\"50%\");", "[line 1] Incomplete format specifier.")]
+    #[case("printf(1);", "[line 1] Format must be a string.")]
+    fn test_statements_native_printf_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("{ fun clock() { return \"local\"; } print clock(); } print clock() > 0;", vec!["local", "true"])]
+    #[case("fun f() { var chr = \"shadow\"; print chr; } f(); print chr(65);", vec!["shadow", "A"])]
+    #[case("{ var ord = 1; { fun ord() { return 2; } print ord(); } print ord; } print ord(\"A\");", vec!["2", "1", "65"])]
+    #[case("fun f(clock) { return clock; } print f(1); print clock() > 0;", vec!["1", "true"])]
+    #[case("fun f(clock) { clock = 2; return clock; } print f(1); print is_callable(clock);", vec!["2", "true"])]
+    #[case("var a = \"global\"; fun f(a) { a = \"changed\"; } f(1); print a;", vec!["global"])]
+    fn test_statements_shadow_native(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
     #[rstest]
     #[case("fun test() { print 10; } test();", vec!["10"])]
     #[case("fun test(a, b, c) { print a + b + c; } test(10, 10, 10);", vec!["30"])]
@@ -563,6 +1445,149 @@ mod tests {
         assert_eq!(expected, run_statement(input).unwrap());
     }
 
+    #[rstest]
+    #[case("var fact = fun (n) { if (n <= 1) return 1; return n * fact(n - 1); }; print fact(5);", vec!["120"])]
+    #[case("fun make() { var fib = fun (n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); }; return fib; } print make()(10);", vec!["55"])]
+    #[case("var add = fun (a, b) { a + b }; print add(1, 2);", vec!["3"])]
+    #[case("print compose(fun (x) { x * 2 }, fun (x) { x + 1 })(3);", vec!["8"])]
+    #[case("print fun () {};", vec!["<fn anonymous>"])]
+    fn test_statements_anonymous_function(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("var x = 1; { var x = x; }", "Variable 'x' used before assignment.")]
+    #[case("var x = 1; { var x = x + 1; print x; }", "Variable 'x' used before assignment.")]
+    #[case("fun f() { var n = 2; { var n = n * 2; } } f();", "Variable 'n' used before assignment.")]
+    fn test_statements_variable_initializer_own_name(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
+    // Copying a function copies the reference to its closure, so both names share the captured state
+    #[rstest]
+    #[case("fun counter() { var n = 0; fun inc() { n = n + 1; return n; } return inc; } var a = counter(); var b = a; b(); b(); print a();", vec!["3"])]
+    #[case("fun counter() { var n = 0; fun inc() { n = n + 1; return n; } return inc; } var a = counter(); var b = counter(); b(); print a();", vec!["1"])]
+    fn test_statements_function_aliasing(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("fun add(a, b) { a + b }", "fun add(a, b) { return a + b; }", "print add(1, 2);")]
+    #[case("fun greet(name) { var greeting = \"hi \"; greeting + name }", "fun greet(name) { var greeting = \"hi \"; return greeting + name; }", "print greet(\"lox\");")]
+    #[case("fun sign(n) { if (n < 0) return -1; n > 0 }", "fun sign(n) { if (n < 0) return -1; return n > 0; }", "print sign(-5); print sign(0); print sign(3);")]
+    #[case("fun outer() { fun inner() { 2 } inner() * 3 }", "fun outer() { fun inner() { return 2; } return inner() * 3; }", "print outer();")]
+    #[case("fun f() { 1; }", "fun f() { return 1; }", "print f();")]
+    #[case("fun f() { return 1; 2; }", "fun f() { return 1; }", "print f();")]
+    #[case("fun noop() { print 1; }", "fun noop() { print 1; return nil; }", "print noop();")]
+    fn test_statements_function_implicit_return(#[case] implicit: &str, #[case] explicit: &str, #[case] usage: &str) {
+        assert_eq!(run_statement(&format!("{} {}", explicit, usage)).unwrap(), run_statement(&format!("{} {}", implicit, usage)).unwrap());
+    }
+
+    #[rstest]
+    #[case("fun f() { 1; } print f();", vec!["1"])]
+    #[case("fun f() { 1 } print f();", vec!["1"])]
+    #[case("fun f(n) { if (n > 0) return \"early\"; \"late\"; } print f(1); print f(0);", vec!["early", "late"])]
+    #[case("fun f() { { 1; } } print f();", vec!["nil"])]
+    fn test_statements_function_trailing_expression(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("return 5;", "[line 1] Error at 'return': Can't return from top-level code.")]
+    #[case("print 1;\nif (true) {\n  return;\n}", "[line 3] Error at 'return': Can't return from top-level code.")]
+    #[case("fun test() { return 1; }\nwhile (true) return test();", "[line 2] Error at 'return': Can't return from top-level code.")]
+    fn test_statements_top_level_return(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case(10, "fun count(n) { if (n > 0) count(n - 1); } count(9); print \"done\";", vec!["done"])]
+    #[case(2, "fun inner() { print \"inner\"; } fun outer() { inner(); } outer();", vec!["inner"])]
+    fn test_statements_recursion_limit(#[case] limit: usize, #[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement_with(InterpreterBuilder::new().recursion_limit(limit), input).unwrap());
+    }
+
+    #[rstest]
+    #[case(10, "fun count(n) { if (n > 0) count(n - 1); } count(10); print \"done\";", "Stack overflow.")]
+    #[case(1, "fun inner() { print \"inner\"; } fun outer() { inner(); } outer();", "Stack overflow.")]
+    fn test_statements_recursion_limit_error(#[case] limit: usize, #[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement_with(InterpreterBuilder::new().recursion_limit(limit), input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case(3, "print 1; print 2; print 3;", vec!["1", "2", "3"])]
+    #[case(7, "for (var i = 0; i < 3; i = i + 1) print i;", vec!["0", "1", "2"])]
+    fn test_statements_max_steps(#[case] limit: usize, #[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement_with(InterpreterBuilder::new().max_steps(limit), input).unwrap());
+    }
+
+    #[rstest]
+    #[case("print false < true;", vec!["true"])]
+    #[case("print true < false;", vec!["false"])]
+    #[case("print true > false;", vec!["true"])]
+    #[case("print true >= true;", vec!["true"])]
+    #[case("print false <= false;", vec!["true"])]
+    #[case("print false > false;", vec!["false"])]
+    #[case("print 1 < 2;", vec!["true"])]
+    fn test_statements_ordered_booleans(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement_with(InterpreterBuilder::new().ordered_booleans(true), input).unwrap());
+    }
+
+    #[rstest]
+    #[case(false, "print false < true;", "Operands must be numbers, got bool and bool.")]
+    #[case(true, "print false < 1;", "Operands must be numbers, got bool and number.")]
+    #[case(true, "print true - false;", "Operands must be numbers, got bool and bool.")]
+    fn test_statements_ordered_booleans_error(#[case] enabled: bool, #[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement_with(InterpreterBuilder::new().ordered_booleans(enabled), input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case(false, "print 0 / 0;", vec!["NaN"])]
+    #[case(false, "print 1 / 0;", vec!["inf"])]
+    #[case(false, "var inf = 1 / 0; print inf - inf;", vec!["NaN"])]
+    #[case(true, "print 1 / 0;", vec!["inf"])]
+    #[case(true, "print 6 / 4;", vec!["1.5"])]
+    fn test_statements_strict_nan(#[case] enabled: bool, #[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement_with(InterpreterBuilder::new().strict_nan(enabled), input).unwrap());
+    }
+
+    #[rstest]
+    #[case("print 0 / 0;")]
+    #[case("var inf = 1 / 0; print inf - inf;")]
+    #[case("print 0 / 0 == 0 / 0;")]
+    fn test_statements_strict_nan_error(#[case] input: &str) {
+        assert_eq!("Operation produced NaN.", run_statement_with(InterpreterBuilder::new().strict_nan(true), input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case(false, "print nil or 5;", vec!["5"])]
+    #[case(true, "print nil or 5;", vec!["true"])]
+    #[case(false, "print 1 and \"a\";", vec!["a"])]
+    #[case(true, "print 1 and \"a\";", vec!["true"])]
+    #[case(true, "print nil and 1;", vec!["false"])]
+    #[case(true, "print false or nil;", vec!["false"])]
+    #[case(true, "print 1 or undefined;", vec!["true"])]
+    #[case(true, "print nil and undefined;", vec!["false"])]
+    fn test_statements_strict_boolean_logic(#[case] enabled: bool, #[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_statement_with(InterpreterBuilder::new().strict_boolean_logic(enabled), input).unwrap());
+    }
+
+    #[rstest]
+    #[case(2, "print 1; print 2; print 3;")]
+    #[case(100, "while (true) {}")]
+    #[case(100, "fun spin() { spin(); } spin();")]
+    #[timeout(Duration::from_millis(500))]
+    fn test_statements_max_steps_error(#[case] limit: usize, #[case] input: &str) {
+        assert_eq!(EXECUTION_LIMIT_EXCEEDED, run_statement_with(InterpreterBuilder::new().max_steps(limit), input).err().unwrap());
+    }
+
+    // Performance guard for the call path, the bound is loose enough for debug builds
+    #[rstest]
+    #[timeout(Duration::from_secs(10))]
+    fn test_statements_fib_regression() {
+        assert_eq!(vec!["6765"], run_statement("fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); } print fib(20);").unwrap());
+    }
+
     #[rstest]
     #[case("print a;", "Undefined variable 'a'.")]
     #[timeout(Duration::from_millis(50))]
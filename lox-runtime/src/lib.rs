@@ -1,3 +1,6 @@
+#[cfg(feature = "counters")]
+pub mod counters;
 pub mod environment;
 pub mod interpreter;
+pub mod natives;
 pub mod value;
\ No newline at end of file
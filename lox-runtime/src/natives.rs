@@ -0,0 +1,217 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::environment::Environment;
+use crate::value::{Callable, Value};
+
+pub fn declare(environment: &mut Environment) {
+    environment.declare("clock".to_string(), Value::Callable(Callable::Native(0, Box::new(clock))));
+    environment.declare("approx_equal".to_string(), Value::Callable(Callable::Native(3, Box::new(approx_equal))));
+    environment.declare("chr".to_string(), Value::Callable(Callable::Native(1, Box::new(chr))));
+    environment.declare("ord".to_string(), Value::Callable(Callable::Native(1, Box::new(ord))));
+    environment.declare("scientific".to_string(), Value::Callable(Callable::Native(2, Box::new(scientific))));
+    environment.declare("expect".to_string(), Value::Callable(Callable::Native(2, Box::new(expect))));
+    environment.declare("compose".to_string(), Value::Callable(Callable::Native(2, Box::new(compose))));
+    environment.declare("is_integer".to_string(), Value::Callable(Callable::Native(1, Box::new(is_integer))));
+    environment.declare("bool".to_string(), Value::Callable(Callable::Native(1, Box::new(bool))));
+    environment.declare("printf".to_string(), Value::Callable(Callable::Variadic(1, Box::new(printf))));
+    environment.declare("getenv".to_string(), Value::Callable(Callable::Native(1, Box::new(getenv))));
+    environment.declare("is_callable".to_string(), Value::Callable(Callable::Native(1, Box::new(is_callable))));
+    environment.declare("seed".to_string(), Value::Callable(Callable::Native(1, Box::new(seed))));
+    environment.declare("random".to_string(), Value::Callable(Callable::Native(0, Box::new(random))));
+}
+
+// State owned by an interpreter and handed to every native it calls
+pub struct Context {
+    // Xorshift state, never zero, seeded from the clock until `seed` is called
+    random_state: u64,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Context {
+            random_state: scramble(match SystemTime::now().duration_since(UNIX_EPOCH) {
+                Ok(duration) => duration.as_nanos() as u64,
+                Err(_) => 0,
+            }),
+        }
+    }
+}
+
+fn scramble(seed: u64) -> u64 {
+    seed.wrapping_mul(0x9E3779B97F4A7C15) | 1
+}
+
+fn clock(_context: &mut Context, _arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs_f64().floor(),
+        Err(_) => 0.0,
+    }))
+}
+
+fn approx_equal(_context: &mut Context, arguments: &[Value]) -> Result<Value, String> {
+    match (&arguments[0], &arguments[1], &arguments[2]) {
+        (Value::Number(left), Value::Number(right), Value::Number(epsilon)) => Ok(Value::Bool((left - right).abs() <= *epsilon)),
+        _ => Err("Arguments must be numbers.".to_string()),
+    }
+}
+
+fn chr(_context: &mut Context, arguments: &[Value]) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::Number(number) => {
+            if number.fract() != 0.0 || *number < 0.0 || *number > u32::MAX as f64 {
+                return Err(format!("Invalid code point {}.", arguments[0]));
+            }
+
+            match char::from_u32(*number as u32) {
+                Some(char) => Ok(Value::String(char.to_string())),
+                None => Err(format!("Invalid code point {}.", arguments[0])),
+            }
+        },
+        _ => Err("Argument must be a number.".to_string()),
+    }
+}
+
+fn ord(_context: &mut Context, arguments: &[Value]) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::String(string) => {
+            let mut chars = string.chars();
+
+            match (chars.next(), chars.next()) {
+                (Some(char), None) => Ok(Value::Number(char as u32 as f64)),
+                _ => Err("Argument must be a single character.".to_string()),
+            }
+        },
+        _ => Err("Argument must be a string.".to_string()),
+    }
+}
+
+// Upper bound for the `digits` argument of `scientific`, larger values would only pad with zeros
+const MAX_SCIENTIFIC_DIGITS: f64 = 100.0;
+
+fn scientific(_context: &mut Context, arguments: &[Value]) -> Result<Value, String> {
+    match (&arguments[0], &arguments[1]) {
+        (Value::Number(number), Value::Number(digits)) => {
+            if digits.fract() != 0.0 || *digits < 0.0 {
+                return Err("Digits must be a non-negative integer.".to_string());
+            }
+
+            if *digits > MAX_SCIENTIFIC_DIGITS {
+                return Err(format!("Digits must be at most {}.", MAX_SCIENTIFIC_DIGITS));
+            }
+
+            Ok(Value::String(format!("{:.*e}", *digits as usize, number)))
+        },
+        _ => Err("Arguments must be numbers.".to_string()),
+    }
+}
+
+fn expect(_context: &mut Context, arguments: &[Value]) -> Result<Value, String> {
+    match arguments[0].is_equal(&arguments[1]) {
+        true => Ok(Value::None),
+        false => Err(format!("Expected {} but got {}.", quoted(&arguments[1]), quoted(&arguments[0]))),
+    }
+}
+
+// Strings are quoted so they can't be mistaken for a number, bool or nil that prints the same
+fn quoted(value: &Value) -> String {
+    match value {
+        Value::String(string) => format!("\"{}\"", string),
+        value => value.to_string(),
+    }
+}
+
+fn compose(_context: &mut Context, arguments: &[Value]) -> Result<Value, String> {
+    match (&arguments[0], &arguments[1]) {
+        (Value::Callable(_), Value::Callable(_)) => Ok(Value::Callable(Callable::Composed(Box::new(arguments[0].clone()), Box::new(arguments[1].clone())))),
+        _ => Err("Arguments must be functions.".to_string()),
+    }
+}
+
+fn is_integer(_context: &mut Context, arguments: &[Value]) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::Number(_) => Ok(Value::Bool(arguments[0].is_integer())),
+        _ => Err("Argument must be a number.".to_string()),
+    }
+}
+
+// There are no environment variables on wasm32-unknown-unknown, so this is always nil in the browser
+fn getenv(_context: &mut Context, arguments: &[Value]) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::String(name) => Ok(match std::env::var(name) {
+            Ok(value) => Value::String(value),
+            Err(_) => Value::None,
+        }),
+        _ => Err("Argument must be a string.".to_string()),
+    }
+}
+
+fn is_callable(_context: &mut Context, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(matches!(arguments[0], Value::Callable(_))))
+}
+
+fn seed(context: &mut Context, arguments: &[Value]) -> Result<Value, String> {
+    match &arguments[0] {
+        Value::Number(number) if arguments[0].is_integer() => {
+            context.random_state = scramble(*number as i64 as u64);
+            Ok(Value::None)
+        },
+        _ => Err("Seed must be an integer.".to_string()),
+    }
+}
+
+fn random(context: &mut Context, _arguments: &[Value]) -> Result<Value, String> {
+    let mut next = context.random_state;
+    next ^= next << 13;
+    next ^= next >> 7;
+    next ^= next << 17;
+    context.random_state = next;
+
+    // The top 53 bits fill the mantissa, giving a float in [0, 1)
+    Ok(Value::Number((next >> 11) as f64 / (1u64 << 53) as f64))
+}
+
+fn bool(_context: &mut Context, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::Bool(arguments[0].is_truthy()))
+}
+
+// Formats like C's printf but returns the string, supporting %d, %f, %s and %%
+fn printf(_context: &mut Context, arguments: &[Value]) -> Result<Value, String> {
+    let format = match &arguments[0] {
+        Value::String(format) => format,
+        _ => return Err("Format must be a string.".to_string()),
+    };
+
+    let mut values = arguments[1..].iter();
+    let mut output = String::new();
+    let mut chars = format.chars();
+
+    while let Some(char) = chars.next() {
+        if char != '%' {
+            output.push(char);
+            continue;
+        }
+
+        let specifier = match chars.next() {
+            Some('%') => {
+                output.push('%');
+                continue;
+            },
+            Some(specifier) => specifier,
+            None => return Err("Incomplete format specifier.".to_string()),
+        };
+
+        let value = values.next().ok_or("Not enough arguments for format string.")?;
+
+        match (specifier, value) {
+            ('d', Value::Number(number)) => output.push_str(&number.trunc().to_string()),
+            ('f', Value::Number(number)) => output.push_str(&format!("{:.6}", number)),
+            ('d' | 'f', value) => return Err(format!("Format '%{}' expects a number, got {}.", specifier, value.type_name())),
+            ('s', value) => output.push_str(&value.to_string()),
+            (specifier, _) => return Err(format!("Unknown format specifier '%{}'.", specifier)),
+        }
+    }
+
+    match values.next() {
+        Some(_) => Err("Too many arguments for format string.".to_string()),
+        None => Ok(Value::String(output)),
+    }
+}
@@ -3,7 +3,9 @@ use std::fmt::Display;
 use std::rc::Rc;
 use lox_syntax::expression::Literal;
 use lox_syntax::statement::Statement;
+use lox_syntax::token::format_number;
 use crate::environment::Environment;
+use crate::natives::Context;
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Value {
@@ -23,6 +25,23 @@ impl Value {
         }
     }
 
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Value::Number(number) => number.is_finite() && number.fract() == 0.0,
+            _ => false,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Bool(_) => "bool",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Callable(_) => "function",
+            Value::None => "nil",
+        }
+    }
+
     pub fn is_equal(&self, other: &Value) -> bool {
         match (self, other) {
             (Value::Bool(left), Value::Bool(right)) => left == right,
@@ -33,6 +52,17 @@ impl Value {
         }
     }
 
+    pub fn to_cache_key(&self) -> Option<String> {
+        match self {
+            Value::Bool(bool) => Some(format!("b:{}", bool)),
+            // Normalize -0 so that values which compare equal share a key
+            Value::Number(number) => Some(format!("n:{:?}", if *number == 0.0 { 0.0 } else { *number })),
+            Value::String(string) => Some(format!("s:{}", string)),
+            Value::None => Some("nil".to_string()),
+            Value::Callable(_) => None,
+        }
+    }
+
     pub fn from_literal(literal: Literal) -> Value {
         match literal {
             Literal::Bool(value) => Value::Bool(value),
@@ -47,12 +77,7 @@ impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::Bool(bool) => write!(f, "{}", bool),
-            Value::Number(number) => {
-                match number.fract() == 0.0 {
-                    true => write!(f, "{:.0}", number),
-                    _ => write!(f, "{}", number),
-                }
-            },
+            Value::Number(number) => write!(f, "{}", format_number(*number)),
             Value::String(string) => write!(f, "{}", string),
             Value::Callable(callable) => write!(f, "{}", callable),
             Value::None => write!(f, "nil"),
@@ -60,18 +85,34 @@ impl Display for Value {
     }
 }
 
+pub type NativeFunction = fn(&mut Context, &[Value]) -> Result<Value, String>;
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum Callable {
-    Native(usize, Box<fn(&Vec<Value>) -> Value>),
+    Native(usize, Box<NativeFunction>),
+    // Native accepting any number of arguments from the given minimum up
+    Variadic(usize, Box<NativeFunction>),
     Function(String, Rc<RefCell<Environment>>, Vec<String>, Box<Statement>),
+    // Calls the second value with the argument and passes its result to the first
+    Composed(Box<Value>, Box<Value>),
 }
 
+impl Callable {
+    pub fn arity(&self) -> usize {
+        match self {
+            Callable::Native(arity, _) | Callable::Variadic(arity, _) => *arity,
+            Callable::Function(_, _, parameters, _) => parameters.len(),
+            Callable::Composed(_, _) => 1,
+        }
+    }
+}
 
 impl Display for Callable {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Callable::Native(_, _) => write!(f, "<native fn>"),
+            Callable::Native(_, _) | Callable::Variadic(_, _) => write!(f, "<native fn>"),
             Callable::Function(name, _, _, _) => write!(f, "<fn {}>", name),
+            Callable::Composed(_, _) => write!(f, "<native fn>"),
         }
     }
 }
@@ -80,4 +121,95 @@ impl Display for Callable {
 pub enum Error {
     Runtime(String),
     Return(Value),
+    Break,
+}
+
+// Return and break are control flow, they only show up here when they escape the code that handles them
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Runtime(error) => write!(f, "{}", error),
+            Error::Return(_) => write!(f, "Received unexpected return value"),
+            Error::Break => write!(f, "Received unexpected break"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use crate::value::{Callable, Error, Value};
+
+    #[rstest]
+    #[case(Value::Number(1.0), Value::Number(1.0))]
+    #[case(Value::Number(0.0), Value::Number(-0.0))]
+    #[case(Value::String("a".into()), Value::String("a".into()))]
+    #[case(Value::Bool(true), Value::Bool(true))]
+    #[case(Value::None, Value::None)]
+    fn test_value_cache_key_equal(#[case] left: Value, #[case] right: Value) {
+        assert!(left.is_equal(&right));
+        assert_eq!(left.to_cache_key(), right.to_cache_key());
+        assert!(left.to_cache_key().is_some());
+    }
+
+    #[rstest]
+    #[case(Value::Number(1.0), Value::String("1".into()))]
+    #[case(Value::Number(1.0), Value::Number(1.5))]
+    #[case(Value::String("true".into()), Value::Bool(true))]
+    #[case(Value::String("nil".into()), Value::None)]
+    fn test_value_cache_key_different(#[case] left: Value, #[case] right: Value) {
+        assert_ne!(left.to_cache_key(), right.to_cache_key());
+    }
+
+    #[rstest]
+    #[case(Value::Number(3.0), true)]
+    #[case(Value::Number(-0.0), true)]
+    #[case(Value::Number(3.5), false)]
+    #[case(Value::Number(f64::INFINITY), false)]
+    #[case(Value::Number(f64::NAN), false)]
+    #[case(Value::String("3".into()), false)]
+    #[case(Value::None, false)]
+    fn test_value_is_integer(#[case] value: Value, #[case] expected: bool) {
+        assert_eq!(expected, value.is_integer());
+    }
+
+    #[rstest]
+    #[case(Value::Bool(false), "bool")]
+    #[case(Value::Number(1.5), "number")]
+    #[case(Value::String("1".into()), "string")]
+    #[case(Value::Callable(Callable::Native(0, Box::new(|_, _| Ok(Value::None)))), "function")]
+    #[case(Value::Callable(Callable::Variadic(1, Box::new(|_, _| Ok(Value::None)))), "function")]
+    #[case(Value::Callable(Callable::Composed(Box::new(Value::None), Box::new(Value::None))), "function")]
+    #[case(Value::None, "nil")]
+    fn test_value_type_name(#[case] value: Value, #[case] expected: &str) {
+        assert_eq!(expected, value.type_name());
+    }
+
+    #[rstest]
+    #[case("10.40", "10.4")]
+    #[case("54.12300", "54.123")]
+    #[case("100.0", "100")]
+    #[case("0.0", "0")]
+    fn test_value_display_number(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, Value::Number(input.parse().unwrap()).to_string());
+    }
+
+    #[rstest]
+    #[case(Error::Runtime("[line 2] Operand must be a number, got nil.".to_string()), "[line 2] Operand must be a number, got nil.")]
+    #[case(Error::Return(Value::Number(1.0)), "Received unexpected return value")]
+    #[case(Error::Break, "Received unexpected break")]
+    fn test_error_display(#[case] error: Error, #[case] expected: &str) {
+        assert_eq!(expected, error.to_string());
+
+        let boxed: Box<dyn std::error::Error> = Box::new(error);
+        assert_eq!(expected, boxed.to_string());
+    }
+
+    #[test]
+    fn test_value_cache_key_function() {
+        let function = Value::Callable(Callable::Native(0, Box::new(|_, _| Ok(Value::None))));
+        assert_eq!(None, function.to_cache_key());
+    }
 }
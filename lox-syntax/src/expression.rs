@@ -1,4 +1,6 @@
 use std::fmt::Display;
+use crate::statement::Statement;
+use crate::token::format_number_literal;
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Literal {
@@ -12,12 +14,7 @@ impl Display for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Literal::Bool(bool) => write!(f, "{}", bool),
-            Literal::Number(number) => {
-                match number.fract() == 0.0 {
-                    true => write!(f, "{:.1}", number),
-                    _ => write!(f, "{}", number),
-                }
-            },
+            Literal::Number(number) => write!(f, "{}", format_number_literal(*number)),
             Literal::String(string) => write!(f, "{}", string),
             Literal::None => write!(f, "nil"),
         }
@@ -28,6 +25,8 @@ impl Display for Literal {
 pub enum UnaryOperation {
     Minus,
     Not,
+    // Postfix `!`, fails on nil and passes any other value through
+    AssertNonNil,
 }
 
 impl Display for UnaryOperation {
@@ -35,6 +34,7 @@ impl Display for UnaryOperation {
         match self {
             UnaryOperation::Minus => write!(f, "-"),
             UnaryOperation::Not => write!(f, "!"),
+            UnaryOperation::AssertNonNil => write!(f, "nonnil"),
         }
     }
 }
@@ -72,32 +72,60 @@ impl Display for BinaryOperation {
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Expression {
-    Literal(Literal),
-    Grouping(Box<Expression>),
-    Unary(UnaryOperation, Box<Expression>),
-    Binary(BinaryOperation, Box<Expression>, Box<Expression>),
-    Variable(String),
-    Assign(String, Box<Expression>),
-    And(Box<Expression>, Box<Expression>),
-    Or(Box<Expression>, Box<Expression>),
-    Call(Box<Expression>, Vec<Expression>),
+    Literal(Literal, usize),
+    Grouping(Box<Expression>, usize),
+    Unary(UnaryOperation, Box<Expression>, usize),
+    Binary(BinaryOperation, Box<Expression>, Box<Expression>, usize),
+    Variable(String, usize),
+    Assign(String, Box<Expression>, usize),
+    And(Box<Expression>, Box<Expression>, usize),
+    Or(Box<Expression>, Box<Expression>, usize),
+    Call(Box<Expression>, Vec<Expression>, usize),
+    Interpolation(Vec<Expression>, usize),
+    // `++`/`--` on a variable, the prefix form (flag set) evaluates to the updated value, the postfix form to the old one
+    Increment(String, BinaryOperation, bool, usize),
+    // Anonymous `fun (a, b) { ... }`, the body is always a block
+    Function(Vec<String>, Box<Statement>, usize),
+}
+
+impl Expression {
+    pub fn line(&self) -> usize {
+        match self {
+            Expression::Literal(_, line)
+            | Expression::Grouping(_, line)
+            | Expression::Unary(_, _, line)
+            | Expression::Binary(_, _, _, line)
+            | Expression::Variable(_, line)
+            | Expression::Assign(_, _, line)
+            | Expression::And(_, _, line)
+            | Expression::Or(_, _, line)
+            | Expression::Call(_, _, line)
+            | Expression::Interpolation(_, line)
+            | Expression::Increment(_, _, _, line)
+            | Expression::Function(_, _, line) => *line,
+        }
+    }
 }
 
 impl Display for Expression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Expression::Literal(literal) => write!(f, "{}", literal),
-            Expression::Grouping(expression) => write!(f, "(group {})", expression),
-            Expression::Unary(operator, expression) => write!(f, "({} {})", operator, expression),
-            Expression::Binary(operator, left, right) => write!(f, "({} {} {})", operator, left, right),
-            Expression::Variable(name) => write!(f, "(variable {})", name),
-            Expression::Assign(name, expression) => write!(f, "(assign {} {})", name, expression),
-            Expression::And(left, right) => write!(f, "({} and {})", left, right),
-            Expression::Or(left, right) => write!(f, "({} or {})", left, right),
-            Expression::Call(callee, arguments) => match arguments.is_empty() {
+            Expression::Literal(literal, _) => write!(f, "{}", literal),
+            Expression::Grouping(expression, _) => write!(f, "(group {})", expression),
+            Expression::Unary(operator, expression, _) => write!(f, "({} {})", operator, expression),
+            Expression::Binary(operator, left, right, _) => write!(f, "({} {} {})", operator, left, right),
+            Expression::Variable(name, _) => write!(f, "(variable {})", name),
+            Expression::Assign(name, expression, _) => write!(f, "(assign {} {})", name, expression),
+            Expression::And(left, right, _) => write!(f, "({} and {})", left, right),
+            Expression::Or(left, right, _) => write!(f, "({} or {})", left, right),
+            Expression::Call(callee, arguments, _) => match arguments.is_empty() {
                 true => write!(f, "(call {})", callee),
                 false => write!(f, "(call {} {})", callee, arguments.iter().map(|statement| statement.to_string()).collect::<Vec<String>>().join(" ")),
             },
+            Expression::Interpolation(parts, _) => write!(f, "(interpolation {})", parts.iter().map(|part| part.to_string()).collect::<Vec<String>>().join(" ")),
+            Expression::Increment(name, operation, true, _) => write!(f, "({}{} (variable {}))", operation, operation, name),
+            Expression::Increment(name, operation, false, _) => write!(f, "((variable {}) {}{})", name, operation, operation),
+            Expression::Function(parameters, body, _) => write!(f, "(function ({}) {})", parameters.join(", "), body),
         }
     }
 }
\ No newline at end of file
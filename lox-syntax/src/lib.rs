@@ -2,4 +2,8 @@ pub mod token;
 pub mod expression;
 pub mod statement;
 pub mod tokenizer;
-pub mod parser;
\ No newline at end of file
+pub mod parser;
+pub mod visitor;
+pub mod resolver;
+
+pub use tokenizer::keywords;
\ No newline at end of file
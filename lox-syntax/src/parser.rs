@@ -1,10 +1,81 @@
 use crate::expression::{BinaryOperation, Expression, Literal, UnaryOperation};
 use crate::statement::Statement;
+use std::fmt::{Display, Formatter};
 use crate::token::{Token, TokenType};
 
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ParseErrorCode {
+    MissingSemicolon,
+    MissingParen,
+    MissingBrace,
+    InvalidAssignmentTarget,
+    LimitExceeded,
+    InvalidBreak,
+    DuplicateParameter,
+    MultipleReturnValues,
+    UnexpectedToken,
+}
+
+// The offending token of a parse error
+#[derive(PartialEq, Debug, Clone)]
+pub enum Found {
+    Token(String),
+    End,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+    pub found: Option<Found>,
+    pub code: ParseErrorCode,
+}
+
+impl ParseError {
+    pub fn new(line: usize, code: ParseErrorCode, message: impl Into<String>) -> Self {
+        ParseError { line, message: message.into(), found: None, code }
+    }
+
+    fn at(token: &Token, code: ParseErrorCode, message: impl Into<String>) -> Self {
+        let found = match token.token {
+            TokenType::Eof => Found::End,
+            _ => Found::Token(token.lexeme.to_string()),
+        };
+
+        ParseError { found: Some(found), ..ParseError::new(token.line, code, message) }
+    }
+
+    // The message with the offending token but without the line prefix
+    pub fn located_message(&self) -> String {
+        match &self.found {
+            Some(Found::End) => format!("Error at end: {}", self.message),
+            Some(Found::Token(lexeme)) => format!("Error at '{}': {}", lexeme, self.message),
+            None => self.message.clone(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[line {}] {}", self.line, self.located_message())
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ReplInput {
+    Statements(Vec<Statement>),
+    Expression(Expression),
+}
+
 pub struct Parser<'a> {
     tokens: Vec<Token<'a>>,
     current: usize,
+    loop_depth: usize,
+    function_body: bool,
+    // Set for each statement directly inside a function body, the last one may leave out its semicolon
+    body_statement: bool,
+    // Inside an unbraced branch of an `if`, where a trailing `else` belongs to the `if` and not to a loop
+    if_branch: bool,
 }
 
 macro_rules! matches {
@@ -25,10 +96,14 @@ impl<'a> Parser<'a> {
         Parser {
             tokens,
             current: 0,
+            loop_depth: 0,
+            function_body: false,
+            body_statement: false,
+            if_branch: false,
         }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Statement>, String> {
+    pub fn parse(&mut self) -> Result<Vec<Statement>, ParseError> {
         let mut statements = Vec::<Statement>::new();
 
         while !self.check(TokenType::Eof) {
@@ -38,42 +113,91 @@ impl<'a> Parser<'a> {
         Ok(statements)
     }
 
-    fn parse_declaration(&mut self) -> Result<Statement, String> {
-        if matches!(self, TokenType::Fun) {
+    // Keeps going after an error by skipping to the next statement boundary
+    pub fn parse_all(&mut self) -> (Vec<Statement>, Vec<ParseError>) {
+        let mut statements = Vec::<Statement>::new();
+        let mut errors = Vec::<ParseError>::new();
+
+        while !self.check(TokenType::Eof) {
+            match self.parse_declaration() {
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    errors.push(error);
+                    self.synchronize();
+                },
+            }
+        }
+
+        (statements, errors)
+    }
+
+    // Falls back to a single bare expression, which a REPL should print
+    pub fn parse_repl(&mut self) -> Result<ReplInput, ParseError> {
+        let error = match self.parse() {
+            Ok(statements) => return Ok(ReplInput::Statements(statements)),
+            Err(error) => error,
+        };
+
+        self.current = 0;
+        self.loop_depth = 0;
+
+        match self.parse_expression() {
+            Ok(expression) if self.is_at_end() => Ok(ReplInput::Expression(expression)),
+            _ => Err(error),
+        }
+    }
+
+    fn parse_declaration(&mut self) -> Result<Statement, ParseError> {
+        // `fun (` starts an anonymous function, which is an expression statement
+        let anonymous = self.tokens.get(self.current + 1).is_some_and(|token| token.token == TokenType::LeftParen);
+
+        if !anonymous && matches!(self, TokenType::Fun) {
             self.parse_function_declaration("function")
         } else {
             self.parse_variable_declaration()
         }
     }
 
-    fn parse_function_declaration(&mut self, kind: &str) -> Result<Statement, String> {
+    fn parse_function_declaration(&mut self, kind: &str) -> Result<Statement, ParseError> {
         let token = self.consume();
 
         let identifier = match token.token {
             TokenType::Identifier(identifier) => identifier.to_string(),
-            _ => return Err(format!("[line {}] Expect {} name.", self.current().line, kind)),
+            _ => return Err(ParseError::new(self.current().line, ParseErrorCode::UnexpectedToken, format!("Expect {} name.", kind))),
         };
 
         if !self.check(TokenType::LeftParen) {
-            return Err(format!("[line {}] Expect '(' after {} name.", self.current().line, kind));
+            return Err(ParseError::new(self.current().line, ParseErrorCode::MissingParen, format!("Expect '(' after {} name.", kind)));
         }
         self.advance();
 
+        let (parameters, body) = self.parse_function_rest(kind)?;
+
+        Ok(Statement::Function(identifier, parameters, Box::new(body)))
+    }
+
+    // Parameters and body of a function, after the opening parenthesis
+    fn parse_function_rest(&mut self, kind: &str) -> Result<(Vec<String>, Statement), ParseError> {
         let mut parameters: Vec<String> = Vec::new();
 
         if !self.check(TokenType::RightParen) {
             loop {
                 if parameters.len() >= 255 {
-                    return Err(format!("[line {}] Can't have more than 255 parameters.", self.current().line));
+                    return Err(ParseError::new(self.current().line, ParseErrorCode::LimitExceeded, "Can't have more than 255 parameters."));
                 }
 
                 let token = self.consume();
+                let line = token.line;
 
                 let identifier = match token.token {
                     TokenType::Identifier(identifier) => identifier.to_string(),
-                    _ => return Err(format!("[line {}] Expect parameter name.", self.current().line)),
+                    _ => return Err(ParseError::new(self.current().line, ParseErrorCode::UnexpectedToken, "Expect parameter name.")),
                 };
 
+                if parameters.contains(&identifier) {
+                    return Err(ParseError::new(line, ParseErrorCode::DuplicateParameter, format!("Duplicate parameter name '{}'.", identifier)));
+                }
+
                 parameters.push(identifier);
 
                 if !matches!(self, TokenType::Comma) {
@@ -83,19 +207,25 @@ impl<'a> Parser<'a> {
         }
 
         if !self.check(TokenType::RightParen) {
-            return Err(format!("[line {}] Expect ')' after parameters.", self.current().line));
+            return Err(ParseError::new(self.current().line, ParseErrorCode::MissingParen, "Expect ')' after parameters."));
         }
         self.advance();
 
         if !self.check(TokenType::LeftBrace) {
-            return Err(format!("[line {}] Expect '{}' before {} body.", self.current().line, "{", kind));
+            return Err(ParseError::new(self.current().line, ParseErrorCode::MissingBrace, format!("Expect '{}' before {} body.", "{", kind)));
         }
 
-        let body = self.parse_statement()?;
-        Ok(Statement::Function(identifier, parameters, Box::new(body)))
+        // Loops surrounding the declaration can't be broken out of from inside the body
+        let loop_depth = self.loop_depth;
+        self.loop_depth = 0;
+        self.function_body = true;
+        let body = self.parse_statement();
+        self.loop_depth = loop_depth;
+
+        Ok((parameters, body?))
     }
 
-    fn parse_variable_declaration(&mut self) -> Result<Statement, String> {
+    fn parse_variable_declaration(&mut self) -> Result<Statement, ParseError> {
         if matches!(self, TokenType::Var) {
             let token = self.consume();
 
@@ -105,73 +235,91 @@ impl<'a> Parser<'a> {
                     expression = Some(self.parse_expression()?);
                 }
 
-                if !self.check(TokenType::Semicolon) {
-                    return Err(format!("[line {}] Expect ';' after value.", self.current().line));
-                }
-
-                self.advance();
+                self.consume_semicolon("Expect ';' after value.")?;
 
                 Ok(Statement::Variable(name.to_string(), expression))
             } else {
-                Err(format!("[line {}] Expect variable name.", token.line))
+                Err(error_at(token, ParseErrorCode::UnexpectedToken, "Expect variable name."))
             }
         } else {
             self.parse_statement()
         }
     }
 
-    fn parse_statement(&mut self) -> Result<Statement, String> {
+    fn parse_statement(&mut self) -> Result<Statement, ParseError> {
+        let body_statement = std::mem::take(&mut self.body_statement);
+
         let statement = if matches!(self, TokenType::Print) {
             let expression = self.parse_expression()?;
+            let mut fallback: Option<Expression> = None;
 
-            if !self.check(TokenType::Semicolon) {
-                return Err(format!("[line {}] Expect ';' after expression.", self.current().line));
+            if matches!(self, TokenType::Else) {
+                fallback = Some(self.parse_expression()?);
             }
 
-            self.advance();
+            self.consume_semicolon("Expect ';' after expression.")?;
 
-            Statement::Print(expression)
+            Statement::Print(expression, fallback)
         } else if matches!(self, TokenType::Return) {
+            let line = self.previous().line;
             let mut expression: Option<Expression> = None;
 
             if !self.check(TokenType::Semicolon) {
                 expression = Some(self.parse_expression()?);
             }
 
-            if !self.check(TokenType::Semicolon) {
-                return Err(format!("[line {}] Expect ';' after return value.", self.current().line));
+            // There are no arrays to package several results into
+            if self.check(TokenType::Comma) {
+                return Err(ParseError::at(self.current(), ParseErrorCode::MultipleReturnValues, "Can't return multiple values."));
             }
-            self.advance();
 
-            Statement::Return(expression)
+            self.consume_semicolon("Expect ';' after return value.")?;
+
+            Statement::Return(expression, line)
         } else if matches!(self, TokenType::LeftBrace) {
+            let line = self.previous().line;
+            let function_body = std::mem::take(&mut self.function_body);
+            let if_branch = std::mem::take(&mut self.if_branch);
             let mut statements: Vec<Statement> = Vec::new();
 
             while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+                self.body_statement = function_body;
                 statements.push(self.parse_declaration()?);
             }
 
+            // The value of a trailing expression statement is what the function returns
+            if function_body {
+                if let Some(Statement::Expression(expression)) = statements.pop_if(|statement| std::matches!(statement, Statement::Expression(_))) {
+                    let line = expression.line();
+                    statements.push(Statement::Return(Some(expression), line));
+                }
+            }
+
             if !self.check(TokenType::RightBrace) {
-                return Err(format!("[line {}] Expect '{}' after block.", self.current().line, '}'));
+                return Err(ParseError::new(self.current().line, ParseErrorCode::MissingBrace, format!("Expect '{}' after block opened on line {}.", '}', line)));
             }
 
             self.advance();
+            self.if_branch = if_branch;
 
             Statement::Block(statements)
         } else if matches!(self, TokenType::If) {
             if !self.check(TokenType::LeftParen) {
-                return Err(format!("[line {}] Expect '(' after 'if'.", self.current().line));
+                return Err(ParseError::new(self.current().line, ParseErrorCode::MissingParen, "Expect '(' after 'if'."));
             }
             self.advance();
 
             let expression = self.parse_expression()?;
 
             if !self.check(TokenType::RightParen) {
-                return Err(format!("[line {}] Expect ')' after if condition.", self.current().line));
+                return Err(ParseError::new(self.current().line, ParseErrorCode::MissingParen, "Expect ')' after if condition."));
             }
             self.advance();
 
-            let if_body = self.parse_statement()?;
+            let if_branch = std::mem::replace(&mut self.if_branch, true);
+            let if_body = self.parse_statement();
+            self.if_branch = if_branch;
+            let if_body = if_body?;
             let mut else_body: Option<Box<Statement>> = None;
 
             if matches!(self, TokenType::Else) {
@@ -181,23 +329,23 @@ impl<'a> Parser<'a> {
             Statement::If(expression, Box::new(if_body), else_body)
         } else if matches!(self, TokenType::While) {
             if !self.check(TokenType::LeftParen) {
-                return Err(format!("[line {}] Expect '(' after 'while'.", self.current().line));
+                return Err(ParseError::new(self.current().line, ParseErrorCode::MissingParen, "Expect '(' after 'while'."));
             }
             self.advance();
 
             let expression = self.parse_expression()?;
 
             if !self.check(TokenType::RightParen) {
-                return Err(format!("[line {}] Expect ')' after condition.", self.current().line));
+                return Err(ParseError::new(self.current().line, ParseErrorCode::MissingParen, "Expect ')' after condition."));
             }
             self.advance();
 
-            let body = self.parse_statement()?;
+            let (body, else_body) = self.parse_loop_body()?;
 
-            Statement::While(expression, Box::new(body))
+            Statement::While(expression, body, else_body)
         } else if matches!(self, TokenType::For) {
             if !self.check(TokenType::LeftParen) {
-                return Err(format!("[line {}] Expect '(' after 'for'.", self.current().line));
+                return Err(ParseError::new(self.current().line, ParseErrorCode::MissingParen, "Expect '(' after 'for'."));
             }
             self.advance();
 
@@ -215,10 +363,7 @@ impl<'a> Parser<'a> {
                 condition = Some(self.parse_expression()?);
             }
 
-            if !self.check(TokenType::Semicolon) {
-                return Err(format!("[line {}] Expect ';' after for condition.", self.current().line));
-            }
-            self.advance();
+            self.consume_semicolon("Expect ';' after for condition.")?;
 
             let mut incrementer: Option<Expression> = None;
 
@@ -227,40 +372,78 @@ impl<'a> Parser<'a> {
             }
 
             if !self.check(TokenType::RightParen) {
-                return Err(format!("[line {}] Expect ')' after for clauses.", self.current().line));
+                return Err(ParseError::new(self.current().line, ParseErrorCode::MissingParen, "Expect ')' after for clauses."));
             }
             self.advance();
 
-            let body = self.parse_statement()?;
+            let (body, else_body) = self.parse_loop_body()?;
+
+            Statement::For(initial, condition, incrementer, body, else_body)
+        } else if matches!(self, TokenType::Break) {
+            if self.loop_depth == 0 {
+                return Err(ParseError::at(self.previous(), ParseErrorCode::InvalidBreak, "Can't use 'break' outside of a loop."));
+            }
+
+            self.consume_semicolon("Expect ';' after 'break'.")?;
+
+            Statement::Break
+        } else if matches!(self, TokenType::Global) {
+            let token = self.consume();
+
+            let name = match token.token {
+                TokenType::Identifier(name) => name.to_string(),
+                _ => return Err(error_at(token, ParseErrorCode::UnexpectedToken, "Expect variable name.")),
+            };
+
+            if !matches!(self, TokenType::Equal) {
+                return Err(ParseError::new(self.current().line, ParseErrorCode::UnexpectedToken, "Expect '=' after global variable name."));
+            }
+
+            let expression = self.parse_expression()?;
+
+            self.consume_semicolon("Expect ';' after value.")?;
 
-            Statement::For(initial, condition, incrementer, Box::new(body))
+            Statement::Global(name, expression)
         } else {
             let expression = self.parse_expression()?;
 
-            if !self.check(TokenType::Semicolon) {
-                return Err(format!("[line {}] Expect ';' after value.", self.current().line));
+            if !(body_statement && self.check(TokenType::RightBrace)) {
+                self.consume_semicolon("Expect ';' after value.")?;
             }
 
-            self.advance();
-
             Statement::Expression(expression)
         };
 
         Ok(statement)
     }
 
-    pub fn parse_expression(&mut self) -> Result<Expression, String> {
+    fn parse_loop_body(&mut self) -> Result<(Box<Statement>, Option<Box<Statement>>), ParseError> {
+        self.loop_depth += 1;
+        let body = self.parse_statement();
+        self.loop_depth -= 1;
+
+        let mut else_body: Option<Box<Statement>> = None;
+
+        if !self.if_branch && matches!(self, TokenType::Else) {
+            else_body = Some(Box::new(self.parse_statement()?));
+        }
+
+        Ok((Box::new(body?), else_body))
+    }
+
+    pub fn parse_expression(&mut self) -> Result<Expression, ParseError> {
         self.parse_assignment()
     }
 
-    fn parse_assignment(&mut self) -> Result<Expression, String> {
+    fn parse_assignment(&mut self) -> Result<Expression, ParseError> {
         let mut expression = self.parse_or()?;
 
         while matches!(self, TokenType::Equal) {
+            let line = self.previous().line;
             expression = match expression {
-                Expression::Variable(name) => Expression::Assign(name, Box::new(self.parse_expression()?)),
+                Expression::Variable(name, _) => Expression::Assign(name, Box::new(self.parse_expression()?), line),
                 _ => {
-                    return Err("Invalid assignment target.".to_string());
+                    return Err(ParseError::at(self.previous(), ParseErrorCode::InvalidAssignmentTarget, "Invalid assignment target."));
                 }
             }
         }
@@ -268,99 +451,138 @@ impl<'a> Parser<'a> {
         Ok(expression)
     }
 
-    fn parse_or(&mut self) -> Result<Expression, String> {
+    fn parse_or(&mut self) -> Result<Expression, ParseError> {
         let mut expression = self.parse_and()?;
 
         while matches!(self, TokenType::Or) {
+            let line = self.previous().line;
             let right = self.parse_and()?;
-            expression = Expression::Or(Box::new(expression), Box::new(right));
+            expression = Expression::Or(Box::new(expression), Box::new(right), line);
         }
 
         Ok(expression)
     }
 
-    fn parse_and(&mut self) -> Result<Expression, String> {
+    fn parse_and(&mut self) -> Result<Expression, ParseError> {
         let mut expression = self.parse_equality()?;
 
         while matches!(self, TokenType::And) {
+            let line = self.previous().line;
             let right = self.parse_equality()?;
-            expression = Expression::And(Box::new(expression), Box::new(right));
+            expression = Expression::And(Box::new(expression), Box::new(right), line);
         }
 
         Ok(expression)
     }
 
-    fn parse_equality(&mut self) -> Result<Expression, String> {
+    fn parse_equality(&mut self) -> Result<Expression, ParseError> {
         let mut expression = self.parse_comparison()?;
 
         while matches!(self, TokenType::EqualEqual, TokenType::BangEqual) {
+            let line = self.previous().line;
             expression = match self.previous().token {
-                TokenType::EqualEqual => Expression::Binary(BinaryOperation::Equal, Box::new(expression), Box::new(self.parse_comparison()?)),
-                _ => Expression::Binary(BinaryOperation::NotEqual, Box::new(expression), Box::new(self.parse_comparison()?)), // Last one can only be BangEqual
+                TokenType::EqualEqual => Expression::Binary(BinaryOperation::Equal, Box::new(expression), Box::new(self.parse_comparison()?), line),
+                _ => Expression::Binary(BinaryOperation::NotEqual, Box::new(expression), Box::new(self.parse_comparison()?), line), // Last one can only be BangEqual
             }
         }
 
         Ok(expression)
     }
 
-    fn parse_comparison(&mut self) -> Result<Expression, String> {
+    fn parse_comparison(&mut self) -> Result<Expression, ParseError> {
         let mut expression = self.parse_term()?;
 
         while matches!(self, TokenType::Greater, TokenType::GreaterEqual, TokenType::Less, TokenType::LessEqual) {
+            let line = self.previous().line;
             expression = match self.previous().token {
-                TokenType::Greater => Expression::Binary(BinaryOperation::Greater, Box::new(expression), Box::new(self.parse_term()?)),
-                TokenType::GreaterEqual => Expression::Binary(BinaryOperation::GreaterEqual, Box::new(expression), Box::new(self.parse_term()?)),
-                TokenType::Less => Expression::Binary(BinaryOperation::Less, Box::new(expression), Box::new(self.parse_term()?)),
-                _ => Expression::Binary(BinaryOperation::LessEqual, Box::new(expression), Box::new(self.parse_term()?)), // Last one can only be LessEqual
+                TokenType::Greater => Expression::Binary(BinaryOperation::Greater, Box::new(expression), Box::new(self.parse_term()?), line),
+                TokenType::GreaterEqual => Expression::Binary(BinaryOperation::GreaterEqual, Box::new(expression), Box::new(self.parse_term()?), line),
+                TokenType::Less => Expression::Binary(BinaryOperation::Less, Box::new(expression), Box::new(self.parse_term()?), line),
+                _ => Expression::Binary(BinaryOperation::LessEqual, Box::new(expression), Box::new(self.parse_term()?), line), // Last one can only be LessEqual
             }
         }
 
         Ok(expression)
     }
 
-    fn parse_term(&mut self) -> Result<Expression, String> {
+    fn parse_term(&mut self) -> Result<Expression, ParseError> {
         let mut expression = self.parse_factor()?;
 
         while matches!(self, TokenType::Plus, TokenType::Minus) {
+            let line = self.previous().line;
             expression = match self.previous().token {
-                TokenType::Plus => Expression::Binary(BinaryOperation::Plus, Box::new(expression), Box::new(self.parse_factor()?)),
-                _ => Expression::Binary(BinaryOperation::Minus, Box::new(expression), Box::new(self.parse_factor()?)), // Last one can only be Minus
+                TokenType::Plus => Expression::Binary(BinaryOperation::Plus, Box::new(expression), Box::new(self.parse_factor()?), line),
+                _ => Expression::Binary(BinaryOperation::Minus, Box::new(expression), Box::new(self.parse_factor()?), line), // Last one can only be Minus
             }
         }
 
         Ok(expression)
     }
 
-    fn parse_factor(&mut self) -> Result<Expression, String> {
+    fn parse_factor(&mut self) -> Result<Expression, ParseError> {
         let mut expression = self.parse_unary()?;
 
         while matches!(self, TokenType::Star, TokenType::Slash) {
+            let line = self.previous().line;
             expression = match self.previous().token {
-                TokenType::Star => Expression::Binary(BinaryOperation::Multiply, Box::new(expression), Box::new(self.parse_unary()?)),
-                _ => Expression::Binary(BinaryOperation::Divide, Box::new(expression), Box::new(self.parse_unary()?)), // Last one can only be Slash
+                TokenType::Star => Expression::Binary(BinaryOperation::Multiply, Box::new(expression), Box::new(self.parse_unary()?), line),
+                _ => Expression::Binary(BinaryOperation::Divide, Box::new(expression), Box::new(self.parse_unary()?), line), // Last one can only be Slash
             }
         }
 
         Ok(expression)
     }
 
-    fn parse_unary(&mut self) -> Result<Expression, String> {
+    fn parse_unary(&mut self) -> Result<Expression, ParseError> {
+        if matches!(self, TokenType::PlusPlus, TokenType::MinusMinus) {
+            let operator = self.current - 1;
+            let line = self.previous().line;
+            let operation = match self.previous().token {
+                TokenType::PlusPlus => BinaryOperation::Plus,
+                _ => BinaryOperation::Minus, // Last one can only be MinusMinus
+            };
+
+            return match (self.parse_unary()?, operation) {
+                (Expression::Variable(name, _), operation) => Ok(Expression::Increment(name, operation, true, line)),
+                // `--` before anything but a variable stays a double negation like `--73`
+                (expression, BinaryOperation::Minus) => Ok(Expression::Unary(UnaryOperation::Minus, Box::new(Expression::Unary(UnaryOperation::Minus, Box::new(expression), line)), line)),
+                _ => Err(ParseError::at(&self.tokens[operator], ParseErrorCode::InvalidAssignmentTarget, "Invalid assignment target.")),
+            };
+        }
+
         if matches!(self, TokenType::Minus, TokenType::Bang) {
+            let line = self.previous().line;
             return Ok(match self.previous().token {
-                TokenType::Minus => Expression::Unary(UnaryOperation::Minus, Box::new(self.parse_unary()?)),
-                _ => Expression::Unary(UnaryOperation::Not, Box::new(self.parse_unary()?)), // Last one can only be Bang
+                TokenType::Minus => Expression::Unary(UnaryOperation::Minus, Box::new(self.parse_unary()?), line),
+                _ => Expression::Unary(UnaryOperation::Not, Box::new(self.parse_unary()?), line), // Last one can only be Bang
             });
         }
 
         self.parse_call()
     }
 
-    fn parse_call(&mut self) -> Result<Expression, String> {
+    fn parse_call(&mut self) -> Result<Expression, ParseError> {
         let mut expression = self.parse_primary()?;
 
         loop {
             if matches!(self, TokenType::LeftParen) {
-                expression = Expression::Call(Box::new(expression), self.finish_call()?);
+                let line = self.previous().line;
+                expression = Expression::Call(Box::new(expression), self.finish_call()?, line);
+            } else if matches!(self, TokenType::Bang) {
+                // A prefix `!` can never follow an operand, so this is always the postfix form
+                let line = self.previous().line;
+                expression = Expression::Unary(UnaryOperation::AssertNonNil, Box::new(expression), line);
+            } else if matches!(self, TokenType::PlusPlus, TokenType::MinusMinus) {
+                let line = self.previous().line;
+                let operation = match self.previous().token {
+                    TokenType::PlusPlus => BinaryOperation::Plus,
+                    _ => BinaryOperation::Minus, // Last one can only be MinusMinus
+                };
+
+                expression = match expression {
+                    Expression::Variable(name, _) => Expression::Increment(name, operation, false, line),
+                    _ => return Err(ParseError::at(self.previous(), ParseErrorCode::InvalidAssignmentTarget, "Invalid assignment target.")),
+                };
             } else {
                 break;
             }
@@ -369,13 +591,13 @@ impl<'a> Parser<'a> {
         Ok(expression)
     }
 
-    fn finish_call(&mut self) -> Result<Vec<Expression>, String> {
+    fn finish_call(&mut self) -> Result<Vec<Expression>, ParseError> {
         let mut arguments: Vec<Expression> = Vec::new();
 
         if !self.check(TokenType::RightParen) {
             loop {
                 if arguments.len() >= 255 {
-                    return Err(format!("[line {}] Can't have more than 255 arguments.", self.current().line));
+                    return Err(ParseError::new(self.current().line, ParseErrorCode::LimitExceeded, "Can't have more than 255 arguments."));
                 }
 
                 arguments.push(self.parse_expression()?);
@@ -387,41 +609,99 @@ impl<'a> Parser<'a> {
         }
 
         if !matches!(self, TokenType::RightParen) {
-            Err(format!("[line {}] Expect ')' after arguments.", self.current().line))
+            Err(ParseError::new(self.current().line, ParseErrorCode::MissingParen, "Expect ')' after arguments."))
         } else {
             Ok(arguments)
         }
     }
 
-    fn parse_primary(&mut self) -> Result<Expression, String> {
+    fn parse_primary(&mut self) -> Result<Expression, ParseError> {
         let token = self.consume();
+        let line = token.line;
         match token.token {
-            TokenType::True => Ok(Expression::Literal(Literal::Bool(true))),
-            TokenType::False => Ok(Expression::Literal(Literal::Bool(false))),
-            TokenType::Number(number) => Ok(Expression::Literal(Literal::Number(number))),
-            TokenType::String(string) => Ok(Expression::Literal(Literal::String(string.to_string()))),
-            TokenType::Nil => Ok(Expression::Literal(Literal::None)),
-            TokenType::Identifier(name) => Ok(Expression::Variable(name.to_string())),
+            TokenType::True => Ok(Expression::Literal(Literal::Bool(true), line)),
+            TokenType::False => Ok(Expression::Literal(Literal::Bool(false), line)),
+            TokenType::Number(number) => Ok(Expression::Literal(Literal::Number(number), line)),
+            TokenType::String(string) => Ok(Expression::Literal(Literal::String(unescape(string)), line)),
+            TokenType::Interpolation(string) => {
+                let mut parts = vec![Expression::Literal(Literal::String(unescape(string)), line)];
+
+                loop {
+                    parts.push(self.parse_expression()?);
+
+                    let token = self.consume();
+                    match token.token {
+                        TokenType::Interpolation(string) => parts.push(Expression::Literal(Literal::String(unescape(string)), token.line)),
+                        TokenType::String(string) => {
+                            parts.push(Expression::Literal(Literal::String(unescape(string)), token.line));
+                            break;
+                        },
+                        _ => return Err(ParseError::new(token.line, ParseErrorCode::MissingBrace, "Expect '}' after interpolated expression.")),
+                    }
+                }
+
+                Ok(Expression::Interpolation(parts, line))
+            },
+            TokenType::Nil => Ok(Expression::Literal(Literal::None, line)),
+            TokenType::Identifier(name) => Ok(Expression::Variable(name.to_string(), line)),
+            TokenType::Fun => {
+                if !self.check(TokenType::LeftParen) {
+                    return Err(error_at(self.current(), ParseErrorCode::MissingParen, "Expect '(' after 'fun'."));
+                }
+                self.advance();
+
+                let (parameters, body) = self.parse_function_rest("function")?;
+
+                Ok(Expression::Function(parameters, Box::new(body), line))
+            },
             TokenType::LeftParen => {
                 let expression = self.parse_expression()?;
 
                 if !self.check(TokenType::RightParen) {
                     let token = self.current();
-                    return Err(match token.token {
-                        TokenType::Eof => format!("[line {}] Error at end: Expect expression.", token.line),
-                        _ => format!("[line {}] Error at '{}': Expect expression.", token.line, token.lexeme)
-                    });
+                    return Err(ParseError::at(token, ParseErrorCode::UnexpectedToken, "Expect expression."));
                 }
 
                 self.advance();
 
-                Ok(Expression::Grouping(Box::new(expression)))
+                Ok(Expression::Grouping(Box::new(expression), line))
             },
-            _ => Err(match token.token {
-                TokenType::Eof => format!("[line {}] Error at end: Expect expression.", token.line),
-                _ => format!("[line {}] Error at '{}': Expect expression.", token.line, token.lexeme)
-            }),
+            _ => Err(ParseError::at(token, ParseErrorCode::UnexpectedToken, "Expect expression.")),
+        }
+    }
+
+    fn synchronize(&mut self) {
+        self.loop_depth = 0;
+        self.function_body = false;
+        self.body_statement = false;
+        self.if_branch = false;
+
+        // The failing token may have been the end of the input, which was consumed already
+        self.current = self.current.min(self.tokens.len() - 1);
+
+        if self.is_at_end() {
+            return;
+        }
+        self.advance();
+
+        while !self.is_at_end() {
+            if self.previous().token == TokenType::Semicolon {
+                return;
+            }
+
+            match self.current().token {
+                TokenType::Class | TokenType::Fun | TokenType::Var | TokenType::For | TokenType::If | TokenType::While | TokenType::Print | TokenType::Return | TokenType::Global => return,
+                _ => self.advance(),
+            }
+        }
+    }
+
+    fn consume_semicolon(&mut self, message: &str) -> Result<(), ParseError> {
+        if !matches!(self, TokenType::Semicolon) {
+            return Err(error_at(self.current(), ParseErrorCode::MissingSemicolon, message));
         }
+
+        Ok(())
     }
 
     fn consume(&mut self) -> &Token<'a> {
@@ -450,25 +730,37 @@ impl<'a> Parser<'a> {
     }
 }
 
+fn error_at(token: &Token, code: ParseErrorCode, message: &str) -> ParseError {
+    match token.token {
+        TokenType::Eof => ParseError::new(token.line, code, message),
+        _ => ParseError::at(token, code, message),
+    }
+}
+
+fn unescape(string: &str) -> String {
+    string.replace("\\${", "${")
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::*;
-    use crate::expression::Expression;
-    use crate::parser::Parser;
+    use crate::expression::{BinaryOperation, Expression, Literal, UnaryOperation};
+    use crate::parser::{Found, ParseError, ParseErrorCode, Parser, ReplInput};
+    use crate::statement::Statement;
     use crate::tokenizer::Scanner;
 
     fn run_expression(source: &str) -> Result<Expression, String> {
         let mut scanner = Scanner::new(source);
         let (tokens, _) = scanner.scan_tokens();
         let mut parser = Parser::new(tokens);
-        parser.parse_expression()
+        parser.parse_expression().map_err(|error| error.to_string())
     }
 
     fn run_statement(source: &str) -> Result<String, String> {
         let mut scanner = Scanner::new(source);
         let (tokens, _) = scanner.scan_tokens();
         let mut parser = Parser::new(tokens);
-        let statements = parser.parse()?;
+        let statements = parser.parse().map_err(|error| error.to_string())?;
         Ok(statements.iter().map(|statement| statement.to_string()).collect::<Vec<String>>().join(" "))
     }
 
@@ -485,6 +777,8 @@ mod tests {
     #[case("123.123", "123.123")]
     #[case("32453454", "32453454.0")]
     #[case("32453454.32453454000", "32453454.32453454")]
+    #[case("1.0000", "1.0")]
+    #[case("007", "7.0")]
     fn test_parser_numbers(#[case] input: &str, #[case] expected: &str) {
         assert_eq!(expected, run_expression(input).unwrap().to_string());
     }
@@ -536,19 +830,157 @@ mod tests {
         assert_eq!(expected, run_expression(input).unwrap().to_string());
     }
 
+    #[rstest]
+    #[case("\"sum = ${1 + 2}\"", "(interpolation sum =  (+ 1.0 2.0) )")]
+    #[case("\"${a}${b}\"", "(interpolation  (variable a)  (variable b) )")]
+    #[case("\"outer ${\"inner ${x}\"}\"", "(interpolation outer  (interpolation inner  (variable x) ) )")]
+    #[case("\"\\${literal}\"", "${literal}")]
+    fn test_parser_interpolation(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_expression(input).unwrap().to_string());
+    }
+
+    #[rstest]
+    #[case("\"a ${1 2}\"", "[line 1] Expect '}' after interpolated expression.")]
+    #[case("\"a ${+} b\"", "[line 1] Error at '+': Expect expression.")]
+    fn test_parser_interpolation_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_expression(input).err().unwrap());
+    }
+
+    fn run_all(source: &str) -> (Vec<String>, Vec<String>) {
+        let mut scanner = Scanner::new(source);
+        let (tokens, _) = scanner.scan_tokens();
+        let (statements, errors) = Parser::new(tokens).parse_all();
+
+        (statements.iter().map(|statement| statement.to_string()).collect(), errors.iter().map(|error| error.to_string()).collect())
+    }
+
+    #[rstest]
+    #[case("print 1;\nprint 2 +;", vec!["(print (; 1.0))"], vec!["[line 2] Error at ';': Expect expression."])]
+    #[case("var = 1;\nprint 1;\nvar b 2;\nprint b;", vec!["(print (; 1.0))", "(print (; (variable b)))"], vec!["[line 1] Error at '=': Expect variable name.", "[line 3] Error at '2': Expect ';' after value."])]
+    #[case("fun f(1) { }\nprint f;", vec!["(print (; (variable f)))"], vec!["[line 1] Expect parameter name."])]
+    #[case("while (true) { break; }\nbreak;\nprint 1 +", vec!["(while (true) (block ((break))))"], vec!["[line 2] Error at 'break': Can't use 'break' outside of a loop.", "[line 3] Error at end: Expect expression."])]
+    #[case("print 1; print 2;", vec!["(print (; 1.0))", "(print (; 2.0))"], vec![])]
+    fn test_parser_parse_all(#[case] input: &str, #[case] statements: Vec<&str>, #[case] errors: Vec<&str>) {
+        let (parsed, reported) = run_all(input);
+
+        assert_eq!(statements, parsed);
+        assert_eq!(errors, reported);
+    }
+
+    fn run_repl(source: &str) -> Result<ReplInput, String> {
+        let mut scanner = Scanner::new(source);
+        let (tokens, _) = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        parser.parse_repl().map_err(|error| error.to_string())
+    }
+
+    #[rstest]
+    #[case("print 1;", "(print (; 1.0))")]
+    #[case("var a = 1; print a;", "(var a = (; 1.0)) (print (; (variable a)))")]
+    #[case("", "")]
+    fn test_parser_repl_statements(#[case] input: &str, #[case] expected: &str) {
+        match run_repl(input).unwrap() {
+            ReplInput::Statements(statements) => assert_eq!(expected, statements.iter().map(|statement| statement.to_string()).collect::<Vec<String>>().join(" ")),
+            input => panic!("Expected statements, got {:?}", input),
+        }
+    }
+
+    #[rstest]
+    #[case("1 + 1", "(+ 1.0 1.0)")]
+    #[case("a = 2", "(assign a 2.0)")]
+    #[case("f(1)", "(call (variable f) 1.0)")]
+    fn test_parser_repl_expression(#[case] input: &str, #[case] expected: &str) {
+        match run_repl(input).unwrap() {
+            ReplInput::Expression(expression) => assert_eq!(expected, expression.to_string()),
+            input => panic!("Expected expression, got {:?}", input),
+        }
+    }
+
+    #[rstest]
+    #[case("1 +", "[line 1] Error at end: Expect expression.")]
+    #[case("1 + 1 2", "[line 1] Error at '2': Expect ';' after value.")]
+    #[case("print 1", "[line 1] Expect ';' after expression.")]
+    fn test_parser_repl_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_repl(input).err().unwrap());
+    }
+
+    #[test]
+    fn test_parser_expression_line() {
+        let expression = run_expression("1\n+\n2").unwrap();
+        assert_eq!(2, expression.line());
+
+        match expression {
+            Expression::Binary(_, left, right, _) => {
+                assert_eq!(1, left.line());
+                assert_eq!(3, right.line());
+            },
+            _ => panic!("Expected binary expression"),
+        }
+    }
+
+    #[rstest]
+    #[case("a", 1)]
+    #[case("\n(\n1)", 2)]
+    #[case("\n\n-a", 3)]
+    #[case("a\n=\n1", 2)]
+    #[case("a\nor b", 2)]
+    #[case("a and\nb", 1)]
+    #[case("f\n(1,\n2)", 2)]
+    #[case("a\n++", 2)]
+    #[case("\"x ${\n1}\"", 1)]
+    fn test_parser_expression_line_variants(#[case] input: &str, #[case] expected: usize) {
+        assert_eq!(expected, run_expression(input).unwrap().line());
+    }
+
     #[rstest]
     #[case("1 or 1", "(1.0 or 1.0)")]
     #[case("1 and 1", "(1.0 and 1.0)")]
     #[case("(1 and 1) or 1", "((group (1.0 and 1.0)) or 1.0)")]
+    #[case("1 == 1 or 2 == 3", "((== 1.0 1.0) or (== 2.0 3.0))")]
+    #[case("a == b and c == d", "((== (variable a) (variable b)) and (== (variable c) (variable d)))")]
+    #[case("a != b or c < d", "((!= (variable a) (variable b)) or (< (variable c) (variable d)))")]
+    #[case("a or b and c", "((variable a) or ((variable b) and (variable c)))")]
+    #[case("a and b or c and d", "(((variable a) and (variable b)) or ((variable c) and (variable d)))")]
+    #[case("a or b or c", "(((variable a) or (variable b)) or (variable c))")]
     fn test_parser_and_or(#[case] input: &str, #[case] expected: &str) {
         assert_eq!(expected, run_expression(input).unwrap().to_string());
     }
 
+    #[rstest]
+    #[case("++i", "(++ (variable i))")]
+    #[case("--i", "(-- (variable i))")]
+    #[case("i++", "((variable i) ++)")]
+    #[case("i--", "((variable i) --)")]
+    #[case("-i++", "(- ((variable i) ++))")]
+    #[case("--73", "(- (- 73.0))")]
+    #[case("--(i)", "(- (- (group (variable i))))")]
+    fn test_parser_increment_decrement(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_expression(input).unwrap().to_string());
+    }
+
+    #[rstest]
+    #[case("++1", "[line 1] Error at '++': Invalid assignment target.")]
+    #[case("++(i)", "[line 1] Error at '++': Invalid assignment target.")]
+    #[case("(i)++", "[line 1] Error at '++': Invalid assignment target.")]
+    #[case("i++++", "[line 1] Error at '++': Invalid assignment target.")]
+    #[case("\n\n++1", "[line 3] Error at '++': Invalid assignment target.")]
+    fn test_parser_increment_decrement_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_expression(input).err().unwrap());
+    }
+
     #[rstest]
     #[case("test()", "(call (variable test))")]
     #[case("test(1)", "(call (variable test) 1.0)")]
     #[case(&format!("test(1{})", ", 1".repeat(254)), &format!("(call (variable test) 1.0{})", " 1.0".repeat(254)))]
     #[case("test(\"test\", a, 2)", "(call (variable test) test (variable a) 2.0)")]
+    #[case("f()()", "(call (call (variable f)))")]
+    #[case("f(1)(2)(a)", "(call (call (call (variable f) 1.0) 2.0) (variable a))")]
+    #[case("x!", "(nonnil (variable x))")]
+    #[case("f()!(1)!", "(nonnil (call (nonnil (call (variable f))) 1.0))")]
+    #[case("!x!", "(! (nonnil (variable x)))")]
+    #[case("x! != nil", "(!= (nonnil (variable x)) nil)")]
+    #[case("x != nil", "(!= (variable x) nil)")]
+    #[case("-x! + 1", "(+ (- (nonnil (variable x))) 1.0)")]
     fn test_parser_call(#[case] input: &str, #[case] expected: &str) {
         assert_eq!(expected, run_expression(input).unwrap().to_string());
     }
@@ -576,6 +1008,8 @@ mod tests {
     #[case("print nil;", "(print (; nil))")]
     #[case("print true;", "(print (; true))")]
     #[case("print test;", "(print (; (variable test)))")]
+    #[case("print test else \"none\";", "(print (; (variable test)) (else none))")]
+    #[case("if (a) print b else c; else print d;", "(if (variable a), (print (; (variable b)) (else (variable c))) (print (; (variable d))))")]
     fn test_parser_statement_print(#[case] input: &str, #[case] expected: &str) {
         assert_eq!(expected, run_statement(input).unwrap());
     }
@@ -588,6 +1022,8 @@ mod tests {
     #[case("var test = test;", "(var test = (; (variable test)))")]
     #[case("test = test;", "(; (assign test (variable test)))")]
     #[case("var test;", "(var test)")]
+    #[case("global test = 1;", "(global test = (; 1.0))")]
+    #[case("{ global test = test; }", "(block ((global test = (; (variable test)))))")]
     fn test_parser_statement_variable(#[case] input: &str, #[case] expected: &str) {
         assert_eq!(expected, run_statement(input).unwrap());
     }
@@ -628,21 +1064,103 @@ mod tests {
         assert_eq!(expected, run_statement(input).unwrap());
     }
 
+    #[rstest]
+    #[case("while (true) break;", "(while (true) (break))")]
+    #[case("while (true) { if (true) break; }", "(while (true) (block ((if true, (break)))))")]
+    #[case("while (false) print 1; else print 2;", "(while (false) (print (; 1.0)) (print (; 2.0)))")]
+    #[case("for (;;) break; else print 2;", "(for (;;) (break) (print (; 2.0)))")]
+    #[case("for (var a = 1;;) break; else print 2;", "(for ((var a = (; 1.0));;) (break) (print (; 2.0)))")]
+    #[case("if (true) while (false) print 1; else print 2;", "(if true, (while (false) (print (; 1.0))) (print (; 2.0)))")]
+    #[case("if (true) for (;;) { break; } else print 2;", "(if true, (for (;;) (block ((break)))) (print (; 2.0)))")]
+    #[case("if (a) while (b) while (c) print 1; else print 2;", "(if (variable a), (while ((variable b)) (while ((variable c)) (print (; 1.0)))) (print (; 2.0)))")]
+    #[case("if (a) if (b) print 1; else while (c) print 2; else print 3;", "(if (variable a), (if (variable b), (print (; 1.0)) (while ((variable c)) (print (; 2.0)))) (print (; 3.0)))")]
+    #[case("if (a) { while (b) print 1; else print 2; }", "(if (variable a), (block ((while ((variable b)) (print (; 1.0)) (print (; 2.0))))))")]
+    #[case("if (a) print 1; else while (b) print 2; else print 3;", "(if (variable a), (print (; 1.0)) (while ((variable b)) (print (; 2.0)) (print (; 3.0))))")]
+    fn test_parser_statement_loop_break_else(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("break;", "[line 1] Error at 'break': Can't use 'break' outside of a loop.")]
+    #[case("while (true) {}\nbreak;", "[line 2] Error at 'break': Can't use 'break' outside of a loop.")]
+    #[case("while (true) print 1; else break;", "[line 1] Error at 'break': Can't use 'break' outside of a loop.")]
+    #[case("while (true) { fun test() { break; } }", "[line 1] Error at 'break': Can't use 'break' outside of a loop.")]
+    #[case("while (true) break", "[line 1] Expect ';' after 'break'.")]
+    fn test_parser_statement_break_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
+    #[rstest]
+    #[case("global 1 = 2;", "[line 1] Error at '1': Expect variable name.")]
+    #[case("global a;", "[line 1] Expect '=' after global variable name.")]
+    #[case("global a = 1", "[line 1] Expect ';' after value.")]
+    fn test_parser_statement_global_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
     #[rstest]
     #[case("fun bar() { print 10; }", "(function bar() (block ((print (; 10.0)))))")]
     #[case("fun bar(a, b, c) { print a + b + c; }", "(function bar(a, b, c) (block ((print (; (+ (+ (variable a) (variable b)) (variable c)))))))")]
+    #[case("fun add(a, b) { a + b }", "(function add(a, b) (block ((return (+ (variable a) (variable b))))))")]
+    #[case("fun f() { print 1; g() }", "(function f() (block ((print (; 1.0)) (return (call (variable g))))))")]
+    #[case("fun f() { 1; }", "(function f() (block ((return 1.0))))")]
+    #[case("fun f() { { 1; } }", "(function f() (block ((block ((; 1.0))))))")]
+    #[case("var f = fun (a) { return a; };", "(var f = (; (function (a) (block ((return (variable a)))))))")]
+    #[case("fun () {};", "(; (function () (block ())))")]
+    #[case("print fun (a, b) { a + b }(1, 2);", "(print (; (call (function (a, b) (block ((return (+ (variable a) (variable b)))))) 1.0 2.0)))")]
     fn test_parser_statement_function(#[case] input: &str, #[case] expected: &str) {
         assert_eq!(expected, run_statement(input).unwrap());
     }
 
+    #[rstest]
+    #[case("return;", "(return)")]
+    #[case("return 5;", "(return 5.0)")]
+    #[case("fun f() { return; }", "(function f() (block ((return))))")]
+    fn test_parser_statement_return(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).unwrap());
+    }
+
+    #[test]
+    fn test_parser_statement_return_line() {
+        let mut scanner = Scanner::new("fun f() {
+  print 1;
+  return
+    5;
+}");
+        let (tokens, _) = scanner.scan_tokens();
+
+        match Parser::new(tokens).parse().unwrap().as_slice() {
+            [Statement::Function(_, _, body)] => match body.as_ref() {
+                Statement::Block(statements) => assert!(std::matches!(statements[1], Statement::Return(Some(_), 3))),
+                body => panic!("unexpected body {}", body),
+            },
+            statements => panic!("unexpected statements {:?}", statements),
+        }
+    }
+
+    #[rstest]
+    #[case("fun bar(a, a) {}", "[line 1] Duplicate parameter name 'a'.")]
+    #[case("fun bar(a, b,\n c, b) {}", "[line 2] Duplicate parameter name 'b'.")]
+    #[case("fun f() { 1 print 2; }", "[line 1] Error at 'print': Expect ';' after value.")]
+    #[case("fun f() { if (true) { 1 } }", "[line 1] Error at '}': Expect ';' after value.")]
+    #[case("{ 1 }", "[line 1] Error at '}': Expect ';' after value.")]
+    #[case("var f = fun a() {};", "[line 1] Error at 'a': Expect '(' after 'fun'.")]
+    #[case("var f = fun (a) {}", "[line 1] Expect ';' after value.")]
+    #[case("fun () {}", "[line 1] Expect ';' after value.")]
+    fn test_parser_statement_function_error(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, run_statement(input).err().unwrap());
+    }
+
     #[rstest]
     #[case("print \"hello world\"", "[line 1] Expect ';' after expression.")]
     #[case("var test = 1", "[line 1] Expect ';' after value.")]
     #[case("var test = (", "[line 1] Error at end: Expect expression.")]
     #[case("var", "[line 1] Expect variable name.")]
-    #[case("{", "[line 1] Expect '}' after block.")]
+    #[case("{", "[line 1] Expect '}' after block opened on line 1.")]
+    #[case("print 1;\n{\n  print 2;\n  {\n  }\n\n\n\n\n", "[line 10] Expect '}' after block opened on line 2.")]
+    #[case("fun f() {\n  if (true) {\n    print 1;\n  }\n", "[line 5] Expect '}' after block opened on line 1.")]
     #[case("1 + 1", "[line 1] Expect ';' after value.")]
-    #[case("2 = 1", "Invalid assignment target.")]
+    #[case("2 = 1", "[line 1] Error at '=': Invalid assignment target.")]
     #[case("if", "[line 1] Expect '(' after 'if'.")]
     #[case("if(1==1", "[line 1] Expect ')' after if condition.")]
     #[case("while", "[line 1] Expect '(' after 'while'.")]
@@ -650,7 +1168,163 @@ mod tests {
     #[case("for", "[line 1] Expect '(' after 'for'.")]
     #[case("for(var a = 1;a < 10", "[line 1] Expect ';' after for condition.")]
     #[case("for(var a = 1;a < 10; a = a + 1", "[line 1] Expect ')' after for clauses.")]
+    #[case("/* a\nmulti-line\ncomment */ print 1", "[line 3] Expect ';' after expression.")]
+    #[case("{ print 1 }", "[line 1] Error at '}': Expect ';' after expression.")]
+    #[case("{ var x = 1 }", "[line 1] Error at '}': Expect ';' after value.")]
+    #[case("{ x = 1\n}", "[line 2] Error at '}': Expect ';' after value.")]
+    #[case("fun f() { return 1 }", "[line 1] Error at '}': Expect ';' after return value.")]
+    #[case("fun f() { return 1, 2; }", "[line 1] Error at ',': Can't return multiple values.")]
+    #[case("fun f(a, b) {\n  return a,\n    b;\n}", "[line 2] Error at ',': Can't return multiple values.")]
+    #[case("while (true) { break }", "[line 1] Error at '}': Expect ';' after 'break'.")]
+    #[case("print 1 2;", "[line 1] Error at '2': Expect ';' after expression.")]
+    #[case("print 007 1.0000;", "[line 1] Error at '1.0000': Expect ';' after expression.")]
+    #[case("var 007 = 1;", "[line 1] Error at '007': Expect variable name.")]
+    #[case("// first\n// second\nvar a = 1 +", "[line 3] Error at end: Expect expression.")]
+    #[case("print 1; /* trailing\n */ print", "[line 2] Error at end: Expect expression.")]
     fn test_parser_statement_error(#[case] input: &str, #[case] expected: &str) {
         assert_eq!(expected, run_statement(input).err().unwrap());
     }
+
+    #[rstest]
+    #[case("print 1", ParseError { line: 1, message: "Expect ';' after expression.".to_string(), found: None, code: ParseErrorCode::MissingSemicolon })]
+    #[case("print 1 2;", ParseError { line: 1, message: "Expect ';' after expression.".to_string(), found: Some(Found::Token("2".to_string())), code: ParseErrorCode::MissingSemicolon })]
+    #[case("\nvar a = 1 +", ParseError { line: 2, message: "Expect expression.".to_string(), found: Some(Found::End), code: ParseErrorCode::UnexpectedToken })]
+    #[case("break;", ParseError { line: 1, message: "Can't use 'break' outside of a loop.".to_string(), found: Some(Found::Token("break".to_string())), code: ParseErrorCode::InvalidBreak })]
+    #[case("2 = 1;", ParseError { line: 1, message: "Invalid assignment target.".to_string(), found: Some(Found::Token("=".to_string())), code: ParseErrorCode::InvalidAssignmentTarget })]
+    fn test_parser_error_fields(#[case] input: &str, #[case] expected: ParseError) {
+        let mut scanner = Scanner::new(input);
+        let (tokens, _) = scanner.scan_tokens();
+        assert_eq!(expected, Parser::new(tokens).parse().err().unwrap());
+    }
+
+    #[rstest]
+    #[case(ParseError::new(3, ParseErrorCode::MissingParen, "Expect ')' after arguments."), "[line 3] Expect ')' after arguments.")]
+    #[case(ParseError { line: 1, message: "Expect ';' after value.".to_string(), found: Some(Found::Token("}".to_string())), code: ParseErrorCode::MissingSemicolon }, "[line 1] Error at '}': Expect ';' after value.")]
+    #[case(ParseError { line: 4, message: "Expect expression.".to_string(), found: Some(Found::End), code: ParseErrorCode::UnexpectedToken }, "[line 4] Error at end: Expect expression.")]
+    #[case(ParseError { line: 3, message: "Invalid assignment target.".to_string(), found: None, code: ParseErrorCode::InvalidAssignmentTarget }, "[line 3] Invalid assignment target.")]
+    fn test_parser_error_display(#[case] error: ParseError, #[case] expected: &str) {
+        assert_eq!(expected, error.to_string());
+    }
+
+    #[rstest]
+    #[case("var a = 1", ParseErrorCode::MissingSemicolon)]
+    #[case("while (true) { break }", ParseErrorCode::MissingSemicolon)]
+    #[case("print (1;", ParseErrorCode::UnexpectedToken)]
+    #[case("print f(1;", ParseErrorCode::MissingParen)]
+    #[case("if true) print 1;", ParseErrorCode::MissingParen)]
+    #[case("fun f() print 1;", ParseErrorCode::MissingBrace)]
+    #[case("{ print 1;", ParseErrorCode::MissingBrace)]
+    #[case("1 + 2 = 3;", ParseErrorCode::InvalidAssignmentTarget)]
+    #[case("var 1 = 2;", ParseErrorCode::UnexpectedToken)]
+    #[case("print +;", ParseErrorCode::UnexpectedToken)]
+    #[case("break;", ParseErrorCode::InvalidBreak)]
+    #[case("fun f() { break; }", ParseErrorCode::InvalidBreak)]
+    #[case("fun f(a, a) {}", ParseErrorCode::DuplicateParameter)]
+    #[case("fun f() { return 1, 2; }", ParseErrorCode::MultipleReturnValues)]
+    #[case("print \"${1\";", ParseErrorCode::MissingBrace)]
+    fn test_parser_error_code(#[case] input: &str, #[case] expected: ParseErrorCode) {
+        let mut scanner = Scanner::new(input);
+        let (tokens, _) = scanner.scan_tokens();
+        assert_eq!(expected, Parser::new(tokens).parse().err().unwrap().code);
+    }
+
+    // Builds random expressions that only nest compound operands inside groupings, so the source rendering is unambiguous
+    struct ExpressionGenerator {
+        state: u64,
+    }
+
+    impl ExpressionGenerator {
+        fn next(&mut self, bound: usize) -> usize {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            (self.state % bound as u64) as usize
+        }
+
+        fn expression(&mut self, depth: usize) -> Expression {
+            if depth == 0 {
+                return self.atom(0);
+            }
+
+            match self.next(6) {
+                0 => Expression::Unary([UnaryOperation::Minus, UnaryOperation::Not, UnaryOperation::AssertNonNil][self.next(3)].clone(), Box::new(self.operand(depth - 1)), 1),
+                1 => {
+                    let operations = [BinaryOperation::Multiply, BinaryOperation::Divide, BinaryOperation::Plus, BinaryOperation::Minus, BinaryOperation::Greater, BinaryOperation::GreaterEqual, BinaryOperation::Less, BinaryOperation::LessEqual, BinaryOperation::Equal, BinaryOperation::NotEqual];
+                    Expression::Binary(operations[self.next(operations.len())].clone(), Box::new(self.operand(depth - 1)), Box::new(self.operand(depth - 1)), 1)
+                },
+                2 => Expression::And(Box::new(self.operand(depth - 1)), Box::new(self.operand(depth - 1)), 1),
+                3 => Expression::Or(Box::new(self.operand(depth - 1)), Box::new(self.operand(depth - 1)), 1),
+                4 => Expression::Assign(self.name(), Box::new(self.expression(depth - 1)), 1),
+                _ => self.atom(depth - 1),
+            }
+        }
+
+        fn operand(&mut self, depth: usize) -> Expression {
+            match self.expression(depth) {
+                expression @ (Expression::Literal(_, _) | Expression::Variable(_, _) | Expression::Grouping(_, _) | Expression::Call(_, _, _) | Expression::Interpolation(_, _)) => expression,
+                expression => Expression::Grouping(Box::new(expression), 1),
+            }
+        }
+
+        fn atom(&mut self, depth: usize) -> Expression {
+            match self.next(if depth == 0 { 5 } else { 8 }) {
+                0 => Expression::Literal(Literal::Bool(self.next(2) == 0), 1),
+                1 => Expression::Literal(Literal::Number(self.next(1000) as f64 / [1.0, 4.0][self.next(2)]), 1),
+                2 => Expression::Literal(Literal::String(["", "a", "hello world", "{}"][self.next(4)].to_string()), 1),
+                3 => Expression::Literal(Literal::None, 1),
+                4 => Expression::Variable(self.name(), 1),
+                5 => Expression::Grouping(Box::new(self.expression(depth - 1)), 1),
+                6 => {
+                    let arguments = (0..self.next(3)).map(|_| self.expression(depth - 1)).collect();
+                    Expression::Call(Box::new(Expression::Variable(self.name(), 1)), arguments, 1)
+                },
+                _ => Expression::Interpolation(vec![
+                    Expression::Literal(Literal::String("a ".to_string()), 1),
+                    self.expression(depth - 1),
+                    Expression::Literal(Literal::String(" b".to_string()), 1),
+                ], 1),
+            }
+        }
+
+        fn name(&mut self) -> String {
+            ["a", "count", "_tmp", "print_value"][self.next(4)].to_string()
+        }
+    }
+
+    fn to_source(expression: &Expression) -> String {
+        match expression {
+            Expression::Literal(Literal::String(string), _) => format!("\"{}\"", string),
+            Expression::Literal(literal, _) => literal.to_string(),
+            Expression::Grouping(expression, _) => format!("({})", to_source(expression)),
+            Expression::Unary(UnaryOperation::AssertNonNil, expression, _) => format!("{}!", to_source(expression)),
+            Expression::Unary(operation, expression, _) => format!("{}{}", operation, to_source(expression)),
+            Expression::Binary(operation, left, right, _) => format!("{} {} {}", to_source(left), operation, to_source(right)),
+            Expression::Variable(name, _) => name.clone(),
+            Expression::Assign(name, expression, _) => format!("{} = {}", name, to_source(expression)),
+            Expression::And(left, right, _) => format!("{} and {}", to_source(left), to_source(right)),
+            Expression::Or(left, right, _) => format!("{} or {}", to_source(left), to_source(right)),
+            Expression::Call(callee, arguments, _) => format!("{}({})", to_source(callee), arguments.iter().map(to_source).collect::<Vec<String>>().join(", ")),
+            Expression::Interpolation(parts, _) => format!("\"{}\"", parts.iter().enumerate().map(|(index, part)| match (index % 2, part) {
+                (0, Expression::Literal(Literal::String(string), _)) => string.clone(),
+                _ => format!("${{{}}}", to_source(part)),
+            }).collect::<String>()),
+            Expression::Increment(name, operation, true, _) => format!("{}{}{}", operation, operation, name),
+            Expression::Increment(name, operation, false, _) => format!("{}{}{}", name, operation, operation),
+            Expression::Function(_, _, _) => unreachable!("The generator does not build functions"),
+        }
+    }
+
+    #[rstest]
+    fn test_parser_roundtrip(#[values(1, 2, 3, 4, 5, 6, 7, 8)] seed: u64) {
+        let mut generator = ExpressionGenerator { state: seed.wrapping_mul(0x9E3779B97F4A7C15) };
+
+        for _ in 0..100 {
+            let expression = generator.expression(4);
+            let source = to_source(&expression);
+            let parsed = run_expression(&source).unwrap_or_else(|error| panic!("{} failed to parse: {}", source, error));
+
+            assert_eq!(expression.to_string(), parsed.to_string(), "{}", source);
+            assert_eq!(expression, parsed, "{}", source);
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,158 @@
+use crate::expression::Expression;
+use crate::statement::Statement;
+use crate::visitor::{walk_expression, walk_statement, ExpressionVisitor, StatementVisitor};
+
+#[derive(Default)]
+pub struct Resolver {
+    warn_unused: bool,
+    scopes: Vec<Vec<(String, bool)>>,
+    warnings: Vec<String>,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn warn_unused(mut self, enabled: bool) -> Self {
+        self.warn_unused = enabled;
+        self
+    }
+
+    pub fn resolve(mut self, statements: &[Statement]) -> Vec<String> {
+        for statement in statements {
+            self.visit_statement(statement);
+        }
+
+        self.warnings
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn end_scope(&mut self) {
+        if let Some(scope) = self.scopes.pop() {
+            if self.warn_unused {
+                for (name, _) in scope.into_iter().filter(|(_, used)| !used) {
+                    self.warnings.push(format!("Unused variable '{}'.", name));
+                }
+            }
+        }
+    }
+
+    // Globals are left untracked, only block and function scopes are checked
+    fn declare(&mut self, name: &str, used: bool) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((name.to_string(), used));
+        }
+    }
+
+    fn resolve_function(&mut self, parameters: &[String], body: &Statement) {
+        self.begin_scope();
+
+        for parameter in parameters {
+            self.declare(parameter, true);
+        }
+
+        // The body block shares the scope of the parameters, like in the interpreter
+        match body {
+            Statement::Block(statements) => {
+                for statement in statements {
+                    self.visit_statement(statement);
+                }
+            },
+            body => self.visit_statement(body),
+        }
+
+        self.end_scope();
+    }
+
+    fn mark_used(&mut self, name: &str) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(entry) = scope.iter_mut().rev().find(|(declared, _)| declared == name) {
+                entry.1 = true;
+                return;
+            }
+        }
+    }
+}
+
+impl ExpressionVisitor for Resolver {
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Variable(name, _) | Expression::Increment(name, _, _, _) => self.mark_used(name),
+            Expression::Function(parameters, body, _) => self.resolve_function(parameters, body),
+            _ => {},
+        }
+
+        walk_expression(self, expression);
+    }
+}
+
+impl StatementVisitor for Resolver {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Variable(name, expression) => {
+                if let Some(expression) = expression {
+                    self.visit_expression(expression);
+                }
+
+                self.declare(name, false);
+            },
+            Statement::Block(statements) => {
+                self.begin_scope();
+
+                for statement in statements {
+                    self.visit_statement(statement);
+                }
+
+                self.end_scope();
+            },
+            Statement::Function(name, parameters, body) => {
+                self.declare(name, true);
+                self.resolve_function(parameters, body);
+            },
+            _ => walk_statement(self, statement),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use crate::parser::Parser;
+    use crate::resolver::Resolver;
+    use crate::tokenizer::Scanner;
+
+    fn run_resolver(source: &str, warn_unused: bool) -> Vec<String> {
+        let mut scanner = Scanner::new(source);
+        let (tokens, _) = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse().unwrap();
+
+        Resolver::new().warn_unused(warn_unused).resolve(&statements)
+    }
+
+    #[rstest]
+    #[case("{ var a = 1; }", vec!["Unused variable 'a'."])]
+    #[case("{ var a = 1; var b = 2; print b; }", vec!["Unused variable 'a'."])]
+    #[case("{ var a = 1; { var a = 2; print a; } }", vec!["Unused variable 'a'."])]
+    #[case("fun test(a) { var b; b = a; }", vec!["Unused variable 'b'."])]
+    #[case("{ var a = 1; print a; }", vec![])]
+    #[case("{ var a = 1; { print a; } }", vec![])]
+    #[case("{ var a = 1; fun show() { print a; } show(); }", vec![])]
+    #[case("fun test(a, b) { }", vec![])]
+    #[case("var a = 1;", vec![])]
+    #[case("{ fun helper() { } }", vec![])]
+    #[case("{ var a = 1; var f = fun () { print a; }; f(); }", vec![])]
+    #[case("var f = fun (a) { var b = a; };", vec!["Unused variable 'b'."])]
+    fn test_resolver_unused_variable(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, run_resolver(input, true));
+    }
+
+    #[test]
+    fn test_resolver_unused_variable_disabled() {
+        assert!(run_resolver("{ var a = 1; }", false).is_empty());
+    }
+}
\ No newline at end of file
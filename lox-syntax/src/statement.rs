@@ -3,59 +3,196 @@ use crate::expression::Expression;
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Statement {
-    Print(Expression),
+    // The fallback of `print value else fallback;` is printed instead of nil
+    Print(Expression, Option<Expression>),
     Variable(String, Option<Expression>),
+    Global(String, Expression),
     Expression(Expression),
     Block(Vec<Statement>),
     If(Expression, Box<Statement>, Option<Box<Statement>>),
-    While(Expression, Box<Statement>),
-    For(Option<Box<Statement>>, Option<Expression>, Option<Expression>, Box<Statement>),
+    While(Expression, Box<Statement>, Option<Box<Statement>>),
+    For(Option<Box<Statement>>, Option<Expression>, Option<Expression>, Box<Statement>, Option<Box<Statement>>),
+    Break,
     Function(String, Vec<String>, Box<Statement>),
-    Return(Option<Expression>),
+    Return(Option<Expression>, usize),
+}
+
+impl Statement {
+    // Multi-line tree view with one node per line, expressions stay in their compact form
+    pub fn pretty(&self, indent: usize) -> String {
+        let padding = "  ".repeat(indent);
+
+        match self {
+            Statement::Print(expression, fallback) => match fallback {
+                Some(fallback) => format!("{}print {} else {}", padding, expression, fallback),
+                None => format!("{}print {}", padding, expression),
+            },
+            Statement::Variable(name, expression) => match expression {
+                Some(expression) => format!("{}var {} = {}", padding, name, expression),
+                None => format!("{}var {}", padding, name),
+            },
+            Statement::Global(name, expression) => format!("{}global {} = {}", padding, name, expression),
+            Statement::Expression(expression) => format!("{}expression {}", padding, expression),
+            Statement::Block(statements) => {
+                let mut lines = vec![format!("{}block", padding)];
+                lines.extend(statements.iter().map(|statement| statement.pretty(indent + 1)));
+                lines.join("\n")
+            },
+            Statement::If(condition, if_body, else_body) => Self::pretty_branches(format!("{}if {}", padding, condition), if_body, else_body, indent),
+            Statement::While(condition, body, else_body) => Self::pretty_branches(format!("{}while {}", padding, condition), body, else_body, indent),
+            Statement::For(initial, condition, incrementer, body, else_body) => {
+                let mut lines = vec![format!("{}for", padding)];
+
+                if let Some(initial) = initial {
+                    lines.push(format!("{}  initializer", padding));
+                    lines.push(initial.pretty(indent + 2));
+                }
+
+                if let Some(condition) = condition {
+                    lines.push(format!("{}  condition {}", padding, condition));
+                }
+
+                if let Some(incrementer) = incrementer {
+                    lines.push(format!("{}  increment {}", padding, incrementer));
+                }
+
+                lines.push(body.pretty(indent + 1));
+
+                if let Some(else_body) = else_body {
+                    lines.push(format!("{}else", padding));
+                    lines.push(else_body.pretty(indent + 1));
+                }
+
+                lines.join("\n")
+            },
+            Statement::Break => format!("{}break", padding),
+            Statement::Function(name, parameters, body) => format!("{}fun {}({})\n{}", padding, name, parameters.join(", "), body.pretty(indent + 1)),
+            Statement::Return(expression, _) => match expression {
+                Some(expression) => format!("{}return {}", padding, expression),
+                None => format!("{}return", padding),
+            },
+        }
+    }
+
+    fn pretty_branches(header: String, body: &Statement, else_body: &Option<Box<Statement>>, indent: usize) -> String {
+        match else_body {
+            Some(else_body) => format!("{}\n{}\n{}else\n{}", header, body.pretty(indent + 1), "  ".repeat(indent), else_body.pretty(indent + 1)),
+            None => format!("{}\n{}", header, body.pretty(indent + 1)),
+        }
+    }
 }
 
 impl Display for Statement {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Statement::Print(expression) => write!(f, "(print (; {}))", expression),
+            Statement::Print(expression, fallback) => match fallback {
+                Some(fallback) => write!(f, "(print (; {}) (else {}))", expression, fallback),
+                None => write!(f, "(print (; {}))", expression),
+            },
             Statement::Variable(name, expression) => match expression {
                 Some(expression) => write!(f, "(var {} = (; {}))", name, expression),
                 None => write!(f, "(var {})", name),
             },
+            Statement::Global(name, expression) => write!(f, "(global {} = (; {}))", name, expression),
             Statement::Expression(expression) => write!(f, "(; {})", expression),
             Statement::Block(statements) => write!(f, "(block ({}))", statements.iter().map(|statement| statement.to_string()).collect::<Vec<String>>().join(" ")),
             Statement::If(expression, if_body, else_body) => match else_body {
                 Some(else_body) => write!(f, "(if {}, {} {})", expression, if_body, else_body),
                 None => write!(f, "(if {}, {})", expression, if_body),  
             },
-            Statement::While(expression, body) => write!(f, "(while ({}) {})", expression, body),
-            Statement::For(initial, condition, incrementer, body) => match initial {
-                Some(initial) => match condition {
-                    Some(condition) => match incrementer {
-                        Some(incrementer) => write!(f, "(for ({};{};{}) {})", initial, condition, incrementer, body),
-                        None => write!(f, "(for ({};{};) {})", initial, condition, body),
-                    }
-                    None => match incrementer {
-                        Some(incrementer) => write!(f, "(for ({};;{}) {})", initial, incrementer, body),
-                        None => write!(f, "(for ({};;) {})", initial, body),
-                    }
-                },
-                None => match condition {
-                    Some(condition) => match incrementer {
-                        Some(incrementer) => write!(f, "(for (;{};{}) {})", condition, incrementer, body),
-                        None => write!(f, "(for (;{};) {})", condition, body),
-                    }
-                    None => match incrementer {
-                        Some(incrementer) => write!(f, "(for (;;{}) {})", incrementer, body),
-                        None => write!(f, "(for (;;) {})", body),
+            Statement::While(expression, body, else_body) => match else_body {
+                Some(else_body) => write!(f, "(while ({}) {} {})", expression, body, else_body),
+                None => write!(f, "(while ({}) {})", expression, body),
+            },
+            Statement::For(initial, condition, incrementer, body, else_body) => {
+                let body = match else_body {
+                    Some(else_body) => format!("{} {}", body, else_body),
+                    None => body.to_string(),
+                };
+
+                match initial {
+                    Some(initial) => match condition {
+                        Some(condition) => match incrementer {
+                            Some(incrementer) => write!(f, "(for ({};{};{}) {})", initial, condition, incrementer, body),
+                            None => write!(f, "(for ({};{};) {})", initial, condition, body),
+                        }
+                        None => match incrementer {
+                            Some(incrementer) => write!(f, "(for ({};;{}) {})", initial, incrementer, body),
+                            None => write!(f, "(for ({};;) {})", initial, body),
+                        }
+                    },
+                    None => match condition {
+                        Some(condition) => match incrementer {
+                            Some(incrementer) => write!(f, "(for (;{};{}) {})", condition, incrementer, body),
+                            None => write!(f, "(for (;{};) {})", condition, body),
+                        }
+                        None => match incrementer {
+                            Some(incrementer) => write!(f, "(for (;;{}) {})", incrementer, body),
+                            None => write!(f, "(for (;;) {})", body),
+                        }
                     }
                 }
             },
+            Statement::Break => write!(f, "(break)"),
             Statement::Function(name, parameters, body) => write!(f, "(function {}({}) {})", name, parameters.iter().map(|statement| statement.to_string()).collect::<Vec<String>>().join(", "), body),
-            Statement::Return(expression) => match expression {
+            Statement::Return(expression, _) => match expression {
                 Some(expression) => write!(f, "(return {})", expression),
                 None => write!(f, "(return)"),
             },
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+    use crate::tokenizer::Scanner;
+
+    fn run_pretty(source: &str) -> String {
+        let mut scanner = Scanner::new(source);
+        let (tokens, _) = scanner.scan_tokens();
+        let statements = Parser::new(tokens).parse().unwrap();
+
+        statements.iter().map(|statement| statement.pretty(0)).collect::<Vec<String>>().join("\n")
+    }
+
+    #[test]
+    fn test_statement_pretty_nested_block() {
+        let source = "var a = 1; { var b = a; { print a + b; } if (b) print b; else { b = 2; } }";
+
+        assert_eq!([
+            "var a = 1.0",
+            "block",
+            "  var b = (variable a)",
+            "  block",
+            "    print (+ (variable a) (variable b))",
+            "  if (variable b)",
+            "    print (variable b)",
+            "  else",
+            "    block",
+            "      expression (assign b 2.0)",
+        ].join("\n"), run_pretty(source));
+    }
+
+    #[test]
+    fn test_statement_pretty_function_and_loops() {
+        let source = "fun f(x, y) { for (var i = 0; i < x; i = i + 1) { if (i == y) break; } while (false) {} else return; }";
+
+        assert_eq!([
+            "fun f(x, y)",
+            "  block",
+            "    for",
+            "      initializer",
+            "        var i = 0.0",
+            "      condition (< (variable i) (variable x))",
+            "      increment (assign i (+ (variable i) 1.0))",
+            "      block",
+            "        if (== (variable i) (variable y))",
+            "          break",
+            "    while false",
+            "      block",
+            "    else",
+            "      return",
+        ].join("\n"), run_pretty(source));
+    }
 }
\ No newline at end of file
@@ -4,9 +4,11 @@
 pub enum TokenType<'a> {
     // Single character tokens
     LeftParen, RightParen, LeftBrace, RightBrace,
-    Comma, Dot, Semicolon, Minus, Plus, Star,
+    Comma, Dot, Semicolon, Star,
 
     // One or two character tokens
+    Minus, MinusMinus,
+    Plus, PlusPlus,
     Slash,
     Equal, EqualEqual,
     Bang, BangEqual,
@@ -15,11 +17,12 @@ pub enum TokenType<'a> {
 
     // Literals
     String(&'a str),
+    Interpolation(&'a str),
     Number(f64),
     Identifier(&'a str),
     
     // Keywords
-    And, Class, Else, False, For, Fun, If, Nil, Or,
+    And, Break, Class, Else, False, For, Fun, Global, If, Nil, Or,
     Print, Return, Super, This, True, Var, While,
     
     Eof,
@@ -29,6 +32,7 @@ impl Display for TokenType<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let token_name = match *self {
             TokenType::String(_) => "STRING".to_string(),
+            TokenType::Interpolation(_) => "INTERPOLATION".to_string(),
             TokenType::Number(_) => "NUMBER".to_string(),
             TokenType::Identifier(_) => "IDENTIFIER".to_string(),
             _ => {
@@ -62,30 +66,135 @@ pub struct Token<'a> {
     pub line: usize,
 }
 
-impl Token<'_> {
-    pub fn new<'a>(token: TokenType<'a>, lexeme: &'a str, line: usize) -> Token<'a> {
+impl<'a> Token<'a> {
+    pub fn new(token: TokenType<'a>, lexeme: &'a str, line: usize) -> Token<'a> {
         Token {
             token,
             lexeme,
             line,
         }
     }
+
+    pub fn number_value(&self) -> Option<f64> {
+        match self.token {
+            TokenType::Number(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    // Interpolation segments are string literal text as well
+    pub fn string_value(&self) -> Option<&'a str> {
+        match self.token {
+            TokenType::String(value) | TokenType::Interpolation(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn identifier(&self) -> Option<&'a str> {
+        match self.token {
+            TokenType::Identifier(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    // One JSON object with the type, lexeme, line and literal value, shared by the CLI and the browser
+    pub fn to_json(&self) -> String {
+        let value = match (self.string_value(), self.number_value()) {
+            (Some(value), _) => json_string(value),
+            (_, Some(value)) => format_number(value),
+            _ => "null".to_string(),
+        };
+
+        format!("{{\"type\": {}, \"lexeme\": {}, \"line\": {}, \"value\": {}}}", json_string(&self.token.to_string()), json_string(self.lexeme), self.line, value)
+    }
+}
+
+fn json_string(string: &str) -> String {
+    let mut json = String::from("\"");
+
+    for char in string.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            char if char.is_control() => json.push_str(&format!("\\u{:04x}", char as u32)),
+            char => json.push(char),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+// Shortest form of a number, so whole numbers have no decimal point and fractions no trailing zeros
+pub fn format_number(number: f64) -> String {
+    match number.fract() == 0.0 {
+        true => format!("{:.0}", number),
+        _ => number.to_string(),
+    }
+}
+
+// Tokens and literals keep a `.0` on whole numbers so the output says it is a number
+pub fn format_number_literal(number: f64) -> String {
+    match number.fract() == 0.0 {
+        true => format!("{}.0", format_number(number)),
+        _ => format_number(number),
+    }
 }
 
 impl Display for Token<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let value = match self.token {
-            TokenType::String(value) => value.to_string(),
-            TokenType::Number(value) => {
-                if value.fract() == 0.0 {
-                    format!("{:.1}", value)
-                } else {
-                    value.to_string()
-                }
-            },
+            TokenType::String(value) | TokenType::Interpolation(value) => value.to_string(),
+            TokenType::Number(value) => format_number_literal(value),
             _ => "null".to_string(),
         };
 
         write!(f, "{} {} {}", self.token, self.lexeme, value)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use crate::expression::Literal;
+    use crate::token::{Token, TokenType};
+
+    #[rstest]
+    #[case("10.40", "10.4")]
+    #[case("54.12300", "54.123")]
+    #[case("100.0", "100.0")]
+    #[case("0.0", "0.0")]
+    fn test_token_and_literal_display_number(#[case] input: &str, #[case] expected: &str) {
+        let number = input.parse().unwrap();
+
+        assert_eq!(format!("NUMBER {} {}", input, expected), Token::new(TokenType::Number(number), input, 1).to_string());
+        assert_eq!(expected, Literal::Number(number).to_string());
+    }
+
+    #[rstest]
+    #[case(TokenType::Number(1.5), Some(1.5), None, None)]
+    #[case(TokenType::String("text"), None, Some("text"), None)]
+    #[case(TokenType::Interpolation("text "), None, Some("text "), None)]
+    #[case(TokenType::Identifier("name"), None, None, Some("name"))]
+    #[case(TokenType::Nil, None, None, None)]
+    #[case(TokenType::Print, None, None, None)]
+    fn test_token_value_accessors(#[case] token_type: TokenType, #[case] number: Option<f64>, #[case] string: Option<&str>, #[case] identifier: Option<&str>) {
+        let token = Token::new(token_type, "lexeme", 1);
+
+        assert_eq!(number, token.number_value());
+        assert_eq!(string, token.string_value());
+        assert_eq!(identifier, token.identifier());
+    }
+
+    #[rstest]
+    #[case(Token::new(TokenType::Number(1.0), "1.0", 2), r#"{"type": "NUMBER", "lexeme": "1.0", "line": 2, "value": 1}"#)]
+    #[case(Token::new(TokenType::Identifier("name"), "name", 1), r#"{"type": "IDENTIFIER", "lexeme": "name", "line": 1, "value": null}"#)]
+    #[case(Token::new(TokenType::String("C:\\dir"), "\"C:\\dir\"", 1), r#"{"type": "STRING", "lexeme": "\"C:\\dir\"", "line": 1, "value": "C:\\dir"}"#)]
+    #[case(Token::new(TokenType::String("say \"hi\""), "\"say \"hi\"\"", 1), r#"{"type": "STRING", "lexeme": "\"say \"hi\"\"", "line": 1, "value": "say \"hi\""}"#)]
+    #[case(Token::new(TokenType::String("a\nb\t"), "\"a\nb\t\"", 1), r#"{"type": "STRING", "lexeme": "\"a\nb\t\"", "line": 1, "value": "a\nb\t"}"#)]
+    fn test_token_to_json(#[case] token: Token, #[case] expected: &str) {
+        assert_eq!(expected, token.to_json());
+    }
+}
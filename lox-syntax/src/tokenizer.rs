@@ -1,13 +1,17 @@
-﻿use phf::{phf_map, Map};
+﻿use std::fmt::{Display, Formatter};
+use std::sync::OnceLock;
+use phf::{phf_map, Map};
 use crate::token::{Token, TokenType};
 
 static KEYWORDS: Map<&'static str, TokenType> = phf_map! {
     "and" => TokenType::And,
+    "break" => TokenType::Break,
     "class" => TokenType::Class,
     "else" => TokenType::Else,
     "false" => TokenType::False,
     "for" => TokenType::For,
     "fun" => TokenType::Fun,
+    "global" => TokenType::Global,
     "if" => TokenType::If,
     "nil" => TokenType::Nil,
     "or" => TokenType::Or,
@@ -20,11 +24,71 @@ static KEYWORDS: Map<&'static str, TokenType> = phf_map! {
     "while" => TokenType::While,
 };
 
+pub fn keywords() -> &'static [&'static str] {
+    static NAMES: OnceLock<Vec<&'static str>> = OnceLock::new();
+
+    NAMES.get_or_init(|| {
+        let mut names: Vec<&'static str> = KEYWORDS.keys().copied().collect();
+        names.sort_unstable();
+        names
+    })
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ScanErrorKind {
+    UnexpectedCharacter(char),
+    UnterminatedString,
+    UnterminatedBlockComment,
+    UnterminatedInterpolation,
+    InvalidNumber,
+    InvalidCharacterBoundary,
+    TabIndentation,
+    InvalidEncoding,
+}
+
+impl Display for ScanErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanErrorKind::UnexpectedCharacter(char) if char.is_control() => write!(f, "Unexpected character: '\\u{{{:04X}}}' (U+{:04X})", *char as u32, *char as u32),
+            ScanErrorKind::UnexpectedCharacter(char) => write!(f, "Unexpected character: {}", char),
+            ScanErrorKind::UnterminatedString => write!(f, "Unterminated string."),
+            ScanErrorKind::UnterminatedBlockComment => write!(f, "Unterminated block comment."),
+            ScanErrorKind::UnterminatedInterpolation => write!(f, "Unterminated interpolation."),
+            ScanErrorKind::InvalidNumber => write!(f, "Invalid number."),
+            ScanErrorKind::InvalidCharacterBoundary => write!(f, "Invalid character boundary."),
+            ScanErrorKind::TabIndentation => write!(f, "Tabs are not allowed; use spaces."),
+            ScanErrorKind::InvalidEncoding => write!(f, "Source is not valid UTF-8."),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ScanError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ScanErrorKind,
+}
+
+impl Display for ScanError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[line {}] Error: {}", self.line, self.kind)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ScanEvent<'a> {
+    Token(Token<'a>),
+    Error(ScanError),
+}
+
 pub struct Scanner<'a> {
     source: &'a str,
     line: usize,
+    line_start: usize,
     current: usize,
     start: usize,
+    forbid_tabs: bool,
+    hash_comments: bool,
 }
 
 impl<'a> Scanner<'a> {
@@ -32,35 +96,95 @@ impl<'a> Scanner<'a> {
         Scanner {
             source,
             line: 0,
+            line_start: 0,
             current: 0,
             start: 0,
+            forbid_tabs: false,
+            hash_comments: false,
         }
     }
 
-    pub fn scan_tokens(&mut self) -> (Vec<Token>, Vec<String>) {
+    // Fails with the position of the first invalid UTF-8 sequence instead of scanning a lossy copy
+    pub fn from_bytes(source: &'a [u8]) -> Result<Self, ScanError> {
+        match std::str::from_utf8(source) {
+            Ok(source) => Ok(Scanner::new(source)),
+            Err(error) => {
+                let valid = std::str::from_utf8(&source[..error.valid_up_to()]).unwrap_or_default();
+                let line_start = valid.rfind('\n').map_or(0, |index| index + 1);
+
+                Err(ScanError {
+                    line: valid.matches('\n').count() + 1,
+                    column: valid[line_start..].chars().count() + 1,
+                    kind: ScanErrorKind::InvalidEncoding,
+                })
+            },
+        }
+    }
+
+    // Reports tabs in the indentation of a line, tabs after the first token are still whitespace
+    pub fn forbid_tabs(mut self, enabled: bool) -> Self {
+        self.forbid_tabs = enabled;
+        self
+    }
+
+    // Lets `#` start a line comment like `//`, otherwise it is an unexpected character outside a leading `#!` line
+    pub fn hash_comments(mut self, enabled: bool) -> Self {
+        self.hash_comments = enabled;
+        self
+    }
+
+    pub fn scan_tokens(&mut self) -> (Vec<Token<'a>>, Vec<String>) {
+        let (tokens, errors) = self.scan_structured();
+
+        (tokens, errors.iter().map(|error| error.to_string()).collect())
+    }
+
+    pub fn scan_structured(&mut self) -> (Vec<Token<'a>>, Vec<ScanError>) {
         let mut tokens: Vec<Token> = Vec::new();
-        let mut errors: Vec<String> = Vec::new();
+        let mut errors: Vec<ScanError> = Vec::new();
+
+        for event in self.scan_detailed() {
+            match event {
+                ScanEvent::Token(token) => tokens.push(token),
+                ScanEvent::Error(error) => errors.push(error),
+            }
+        }
+
+        (tokens, errors)
+    }
+
+    pub fn scan_detailed(&mut self) -> Vec<ScanEvent<'a>> {
+        let mut events: Vec<ScanEvent> = Vec::new();
         self.current = 0;
         self.line = 1;
+        self.line_start = 0;
 
-        let mut peekable = self.source.chars().peekable();
-
-        loop {
-            if self.source.len() <= self.current {
-                break;
-            }
+        // An interpreter line like `#!/usr/bin/env lox` is skipped whether or not `#` starts comments
+        if self.source.starts_with("#!") {
+            self.current = self.source.find('\n').unwrap_or(self.source.len());
+        }
 
-            let token = peekable.next().unwrap();
+        let mut peekable = self.source[self.current..].chars().peekable();
+        // Open brace depth of each `${` interpolation the scanner is currently inside
+        let mut interpolations: Vec<usize> = Vec::new();
 
+        while let Some(token) = peekable.next() {
             self.start = self.current;
             self.current += token.len_utf8();
 
             let should_ignore = match token {
                 ' ' => true,
                 '\r' => true,
-                '\t' => true,
+                '\t' => {
+                    // Only the first tab of an indentation is reported
+                    if self.forbid_tabs && self.source[self.line_start..self.start].chars().all(|char| char == ' ') {
+                        events.push(self.error(self.start, ScanErrorKind::TabIndentation));
+                    }
+                    true
+                },
                 '\n' => {
                     self.line += 1;
+                    self.line_start = self.current;
                     true
                 },
                 _ => false,
@@ -70,22 +194,47 @@ impl<'a> Scanner<'a> {
                 continue;
             }
 
+            // A backslash right before a line break continues the line
+            if token == '\\' && matches!(peekable.peek(), Some('\n') | Some('\r')) {
+                if peekable.next_if_eq(&'\r').is_some() {
+                    self.current += '\r'.len_utf8();
+                }
+
+                if peekable.next_if_eq(&'\n').is_some() {
+                    self.current += '\n'.len_utf8();
+                    self.line += 1;
+                    self.line_start = self.current;
+                }
+
+                continue;
+            }
+
+            let resumes_string = token == '}' && interpolations.last() == Some(&0);
+
+            if resumes_string {
+                interpolations.pop();
+            } else if let Some(depth) = interpolations.last_mut() {
+                match token {
+                    '{' => *depth += 1,
+                    '}' => *depth -= 1,
+                    _ => {},
+                }
+            }
+
             let token_type = match token {
                 '(' => Some(TokenType::LeftParen),
                 ')' => Some(TokenType::RightParen),
                 '{' => Some(TokenType::LeftBrace),
-                '}' => Some(TokenType::RightBrace),
+                '}' if !resumes_string => Some(TokenType::RightBrace),
                 ',' => Some(TokenType::Comma),
                 '.' => Some(TokenType::Dot),
                 ';' => Some(TokenType::Semicolon),
-                '-' => Some(TokenType::Minus),
-                '+' => Some(TokenType::Plus),
                 '*' => Some(TokenType::Star),
                 _ => None,
             };
 
             if let Some(token_type) = token_type {
-                tokens.push(Token::new(token_type, &self.source[self.start..self.current], self.line));
+                events.push(self.token(token_type, self.line));
                 continue;
             }
 
@@ -94,32 +243,65 @@ impl<'a> Scanner<'a> {
                 ('!', Some('=')) => Some(TokenType::BangEqual),
                 ('<', Some('=')) => Some(TokenType::LessEqual),
                 ('>', Some('=')) => Some(TokenType::GreaterEqual),
+                ('-', Some('-')) => Some(TokenType::MinusMinus),
+                ('+', Some('+')) => Some(TokenType::PlusPlus),
                 (_, _) => None,
             };
 
             if let Some(token_type) = token_type {
-                self.current += 1;
-                peekable.next();
-                tokens.push(Token::new(token_type, &self.source[self.start..self.current], self.line));
+                if let Some(next) = peekable.next() {
+                    self.current += next.len_utf8();
+                }
+                events.push(self.token(token_type, self.line));
                 continue;
             }
 
-            if token == '/' && peekable.peek() == Some(&'/') {
-                peekable.next(); // Consume second slash
-                self.current += token.len_utf8();
+            if (token == '/' && peekable.peek() == Some(&'/')) || (token == '#' && self.hash_comments) {
+                if token == '/' {
+                    peekable.next(); // Consume second slash
+                    self.current += token.len_utf8();
+                }
 
                 for token in peekable.by_ref() {
                     self.current += token.len_utf8();
                     if token == '\n' {
                         self.line += 1;
+                        self.line_start = self.current;
+                        break;
+                    }
+                }
+
+                continue;
+            }
+
+            if token == '/' && peekable.peek() == Some(&'*') {
+                peekable.next(); // Consume star
+                self.current += '*'.len_utf8();
+
+                let mut terminated = false;
+                while let Some(token) = peekable.next() {
+                    self.current += token.len_utf8();
+                    if token == '\n' {
+                        self.line += 1;
+                        self.line_start = self.current;
+                    } else if token == '*' && peekable.peek() == Some(&'/') {
+                        peekable.next(); // Consume closing slash
+                        self.current += '/'.len_utf8();
+                        terminated = true;
                         break;
                     }
                 }
 
+                if !terminated {
+                    events.push(self.error(self.current, ScanErrorKind::UnterminatedBlockComment));
+                }
+
                 continue;
             }
 
             let token_type = match token {
+                '-' => Some(TokenType::Minus),
+                '+' => Some(TokenType::Plus),
                 '/' => Some(TokenType::Slash),
                 '=' => Some(TokenType::Equal),
                 '!' => Some(TokenType::Bang),
@@ -129,23 +311,39 @@ impl<'a> Scanner<'a> {
             };
 
             if let Some(token_type) = token_type {
-                tokens.push(Token::new(token_type, &self.source[self.start..self.current], self.line));
+                events.push(self.token(token_type, self.line));
                 continue;
             }
 
-            if token == '"' {
+            // A closing interpolation brace continues the string it was opened in
+            if token == '"' || resumes_string {
                 let line_start = self.line;
+                let mut escaped = false;
                 loop {
                     if let Some(token) = peekable.next() {
                         self.current += token.len_utf8();
                         if token == '"' {
-                            tokens.push(Token::new(TokenType::String(&self.source[self.start + 1..self.current - 1]), &self.source[self.start..self.current], line_start));
+                            events.push(match self.source.get(self.start + 1..self.current - 1) {
+                                Some(value) => self.token(TokenType::String(value), line_start),
+                                None => self.error(self.start, ScanErrorKind::InvalidCharacterBoundary),
+                            });
+                            break;
+                        } else if token == '$' && !escaped && peekable.peek() == Some(&'{') {
+                            peekable.next(); // Consume opening brace
+                            self.current += '{'.len_utf8();
+                            events.push(match self.source.get(self.start + 1..self.current - 2) {
+                                Some(value) => self.token(TokenType::Interpolation(value), line_start),
+                                None => self.error(self.start, ScanErrorKind::InvalidCharacterBoundary),
+                            });
+                            interpolations.push(0);
                             break;
                         } else if token == '\n' {
                             self.line += 1;
+                            self.line_start = self.current;
                         }
+                        escaped = token == '\\';
                     } else {
-                        errors.push(format!("[line {}] Error: Unterminated string.", self.line));
+                        events.push(self.error(self.current, ScanErrorKind::UnterminatedString));
                         break;
                     }
                 }
@@ -156,22 +354,24 @@ impl<'a> Scanner<'a> {
                 let mut found_dot = false;
                 while let Some(token) = peekable.peek() {
                     if token.is_ascii_digit() {
-                        self.current += 1;
+                        self.current += token.len_utf8();
                         peekable.next();
                     } else if *token == '.' && !found_dot {
                         found_dot = true;
+                        self.current += token.len_utf8();
                         peekable.next();
-                        self.current += 1;
                     } else {
                         break;
                     }
                 }
 
-                let value: f64 = self.source[self.start..self.current]
-                    .parse()
-                    .unwrap();
+                let value = self.source.get(self.start..self.current)
+                    .and_then(|lexeme| lexeme.parse::<f64>().ok());
 
-                tokens.push(Token::new(TokenType::Number(value), &self.source[self.start..self.current], self.line));
+                events.push(match value {
+                    Some(value) => self.token(TokenType::Number(value), self.line),
+                    None => self.error(self.start, ScanErrorKind::InvalidNumber),
+                });
 
                 continue;
             }
@@ -179,36 +379,61 @@ impl<'a> Scanner<'a> {
             if token.is_ascii_alphabetic() || token == '_' {
                 while let Some(token) = peekable.peek() {
                     if token.is_ascii_alphanumeric() || *token == '_' {
+                        self.current += token.len_utf8();
                         peekable.next();
-                        self.current += 1;
                     } else {
                         break;
                     }
                 }
 
-                if let Some(token_type) = KEYWORDS.get(&self.source[self.start..self.current]) {
-                    tokens.push(Token::new(token_type.clone(), &self.source[self.start..self.current], self.line));
-                } else {
-                    tokens.push(Token::new(TokenType::Identifier(&self.source[self.start..self.current]), &self.source[self.start..self.current], self.line));
-                }
+                events.push(match self.source.get(self.start..self.current) {
+                    Some(lexeme) => match KEYWORDS.get(lexeme) {
+                        Some(token_type) => self.token(token_type.clone(), self.line),
+                        None => self.token(TokenType::Identifier(lexeme), self.line),
+                    },
+                    None => self.error(self.start, ScanErrorKind::InvalidCharacterBoundary),
+                });
 
                 continue;
             }
 
-            errors.push(format!("[line {}] Error: Unexpected character: {}", self.line, token));
+            events.push(self.error(self.start, ScanErrorKind::UnexpectedCharacter(token)));
         }
 
-        tokens.push(Token::new(TokenType::Eof, "", self.line));
+        if !interpolations.is_empty() {
+            events.push(self.error(self.current, ScanErrorKind::UnterminatedInterpolation));
+        }
 
-        (tokens, errors)
+        events.push(ScanEvent::Token(Token::new(TokenType::Eof, "", self.line)));
+
+        events
+    }
+
+    fn token(&self, token_type: TokenType<'a>, line: usize) -> ScanEvent<'a> {
+        match self.source.get(self.start..self.current) {
+            Some(lexeme) => ScanEvent::Token(Token::new(token_type, lexeme, line)),
+            None => self.error(self.start, ScanErrorKind::InvalidCharacterBoundary),
+        }
+    }
+
+    fn error(&self, position: usize, kind: ScanErrorKind) -> ScanEvent<'a> {
+        let column = self.source.get(self.line_start..position)
+            .map_or(0, |prefix| prefix.chars().count());
+
+        ScanEvent::Error(ScanError {
+            line: self.line,
+            column: column + 1,
+            kind,
+        })
     }
 }
 
 
 #[cfg(test)]
 mod tests {
+    use rstest::*;
     use crate::token::{Token, TokenType};
-    use crate::tokenizer::Scanner;
+    use crate::tokenizer::{keywords, ScanError, ScanErrorKind, ScanEvent, Scanner};
 
     #[test]
     fn test_lexer_single_character_tokens() {
@@ -267,6 +492,23 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_lexer_increment_and_decrement() {
+        let source = "++i--+-";
+        let mut scanner = Scanner::new(source);
+        let (tokens, errors) = scanner.scan_tokens();
+
+        assert!(errors.is_empty());
+        assert_eq!(tokens, vec![
+            Token { token: TokenType::PlusPlus, lexeme: "++", line: 1 },
+            Token { token: TokenType::Identifier("i"), lexeme: "i", line: 1 },
+            Token { token: TokenType::MinusMinus, lexeme: "--", line: 1 },
+            Token { token: TokenType::Plus, lexeme: "+", line: 1 },
+            Token { token: TokenType::Minus, lexeme: "-", line: 1 },
+            Token { token: TokenType::Eof, lexeme: "", line: 1 }
+        ]);
+    }
+
     #[test]
     fn test_lexer_lexical_errors() {
         let source = ",.$(#";
@@ -285,6 +527,155 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_lexer_detailed_events_in_source_order() {
+        let source = "1 $ 2\n  @\"a";
+        let mut scanner = Scanner::new(source);
+        let events = scanner.scan_detailed();
+
+        assert_eq!(events, vec![
+            ScanEvent::Token(Token { token: TokenType::Number(1.0), lexeme: "1", line: 1 }),
+            ScanEvent::Error(ScanError { line: 1, column: 3, kind: ScanErrorKind::UnexpectedCharacter('$') }),
+            ScanEvent::Token(Token { token: TokenType::Number(2.0), lexeme: "2", line: 1 }),
+            ScanEvent::Error(ScanError { line: 2, column: 3, kind: ScanErrorKind::UnexpectedCharacter('@') }),
+            ScanEvent::Error(ScanError { line: 2, column: 6, kind: ScanErrorKind::UnterminatedString }),
+            ScanEvent::Token(Token { token: TokenType::Eof, lexeme: "", line: 2 }),
+        ]);
+    }
+
+    #[test]
+    fn test_lexer_random_multi_byte_input_does_not_panic() {
+        let alphabet: Vec<char> = "aZ_09.\"/=!<>+-;(){} \t\r\né€ß😀中\u{0301}".chars().collect();
+        let mut seed: u64 = 0x2545F4914F6CDD1D;
+
+        for _ in 0..2000 {
+            let mut source = String::new();
+            for _ in 0..(seed % 24) {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                source.push(alphabet[(seed % alphabet.len() as u64) as usize]);
+            }
+
+            let mut scanner = Scanner::new(&source);
+            let events = scanner.scan_detailed();
+
+            for event in events.iter() {
+                if let ScanEvent::Token(token) = event {
+                    assert!(source.contains(token.lexeme));
+                }
+            }
+            assert!(matches!(events.last(), Some(ScanEvent::Token(Token { token: TokenType::Eof, .. }))));
+        }
+    }
+
+    #[rstest]
+    #[case("@", "[line 1] Error: Unexpected character: @")]
+    #[case("\u{7}", "[line 1] Error: Unexpected character: '\\u{0007}' (U+0007)")]
+    #[case("1 +\n\u{1b}", "[line 2] Error: Unexpected character: '\\u{001B}' (U+001B)")]
+    #[case("\u{85}", "[line 1] Error: Unexpected character: '\\u{0085}' (U+0085)")]
+    #[case("\n  \"open", "[line 2] Error: Unterminated string.")]
+    #[case("/* open", "[line 1] Error: Unterminated block comment.")]
+    #[case("\"${1", "[line 1] Error: Unterminated interpolation.")]
+    fn test_lexer_structured_errors_format(#[case] input: &str, #[case] expected: &str) {
+        let (_, errors) = Scanner::new(input).scan_structured();
+        let (_, messages) = Scanner::new(input).scan_tokens();
+
+        assert_eq!(vec![expected], messages);
+        assert_eq!(messages, errors.iter().map(|error| error.to_string()).collect::<Vec<String>>());
+    }
+
+    #[rstest]
+    #[case("print 1;\n\tprint 2;", vec!["[line 2] Error: Tabs are not allowed; use spaces."])]
+    #[case("{\n  \t\tprint 1;\n}", vec!["[line 2] Error: Tabs are not allowed; use spaces."])]
+    #[case("\tvar a;\n\tvar b;", vec!["[line 1] Error: Tabs are not allowed; use spaces.", "[line 2] Error: Tabs are not allowed; use spaces."])]
+    #[case("{\n    print 1;\n}", vec![])]
+    #[case("print 1;\tprint 2;", vec![])]
+    #[case("print \"\ta\";", vec![])]
+    #[case("/*\n\tcomment\n*/", vec![])]
+    fn test_lexer_forbid_tabs(#[case] input: &str, #[case] expected: Vec<&str>) {
+        let (_, errors) = Scanner::new(input).forbid_tabs(true).scan_tokens();
+        let (_, default_errors) = Scanner::new(input).scan_tokens();
+
+        assert_eq!(expected, errors);
+        assert!(default_errors.is_empty());
+    }
+
+    #[rstest]
+    #[case("# comment", vec!["EOF  null"])]
+    #[case("print 1; # comment\nprint 2;", vec!["PRINT print null", "NUMBER 1 1.0", "SEMICOLON ; null", "PRINT print null", "NUMBER 2 2.0", "SEMICOLON ; null", "EOF  null"])]
+    #[case("#!/usr/bin/env lox\n// comment\nnil", vec!["NIL nil null", "EOF  null"])]
+    #[case("\"# not a comment\"", vec!["STRING \"# not a comment\" # not a comment", "EOF  null"])]
+    fn test_lexer_hash_comments(#[case] input: &str, #[case] expected: Vec<&str>) {
+        let (tokens, errors) = Scanner::new(input).hash_comments(true).scan_tokens();
+
+        assert!(errors.is_empty());
+        assert_eq!(expected, tokens.iter().map(|token| token.to_string()).collect::<Vec<String>>());
+    }
+
+    #[test]
+    fn test_lexer_hash_comments_disabled() {
+        let (_, errors) = Scanner::new("# comment").scan_tokens();
+
+        assert_eq!(Some(&"[line 1] Error: Unexpected character: #".to_string()), errors.first());
+    }
+
+    #[rstest]
+    #[case("#!/usr/bin/env lox", vec!["EOF  null"])]
+    #[case("#!/usr/bin/env lox\nprint 1;", vec!["PRINT print null", "NUMBER 1 1.0", "SEMICOLON ; null", "EOF  null"])]
+    #[case("#!lox\r\nnil", vec!["NIL nil null", "EOF  null"])]
+    fn test_lexer_shebang(#[case] input: &str, #[case] expected: Vec<&str>) {
+        let (tokens, errors) = Scanner::new(input).scan_tokens();
+
+        assert!(errors.is_empty());
+        assert_eq!(expected, tokens.iter().map(|token| token.to_string()).collect::<Vec<String>>());
+    }
+
+    #[test]
+    fn test_lexer_shebang_line() {
+        let (tokens, _) = Scanner::new("#!lox\nnil").scan_tokens();
+
+        assert_eq!(2, tokens[0].line);
+    }
+
+    #[rstest]
+    #[case(" #!/usr/bin/env lox")]
+    #[case("nil\n#!/usr/bin/env lox")]
+    fn test_lexer_shebang_not_first(#[case] input: &str) {
+        let (_, errors) = Scanner::new(input).scan_tokens();
+
+        assert!(errors.iter().any(|error| error.ends_with("Unexpected character: #")));
+    }
+
+    #[rstest]
+    #[case(b"print \"\xff\";", ScanError { line: 1, column: 8, kind: ScanErrorKind::InvalidEncoding })]
+    #[case(b"print 1;\n// \xc3\xa9 \xc3\n", ScanError { line: 2, column: 6, kind: ScanErrorKind::InvalidEncoding })]
+    fn test_lexer_from_bytes_invalid(#[case] input: &[u8], #[case] expected: ScanError) {
+        let error = Scanner::from_bytes(input).err().unwrap();
+
+        assert_eq!(expected, error);
+        assert_eq!(format!("[line {}] Error: Source is not valid UTF-8.", expected.line), error.to_string());
+    }
+
+    #[test]
+    fn test_lexer_from_bytes() {
+        let (tokens, errors) = Scanner::from_bytes("print \"é\";".as_bytes()).unwrap().scan_tokens();
+
+        assert!(errors.is_empty());
+        assert_eq!(Token { token: TokenType::String("é"), lexeme: "\"é\"", line: 1 }, tokens[1]);
+    }
+
+    #[test]
+    fn test_lexer_structured_errors() {
+        let (tokens, errors) = Scanner::new("1 é\n\"a").scan_structured();
+
+        assert_eq!(2, tokens.len());
+        assert_eq!(errors, vec![
+            ScanError { line: 1, column: 3, kind: ScanErrorKind::UnexpectedCharacter('é') },
+            ScanError { line: 2, column: 3, kind: ScanErrorKind::UnterminatedString },
+        ]);
+    }
+
     #[test]
     fn test_lexer_whitespaces() {
         let source = " \t\r\n";
@@ -356,6 +747,25 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_lexer_literal_number_keeps_lexeme() {
+        let source = "007 1.0000";
+        let mut scanner = Scanner::new(source);
+        let (tokens, errors) = scanner.scan_tokens();
+
+        assert!(errors.is_empty());
+        assert_eq!(tokens, vec![
+            Token { token: TokenType::Number(7.0), lexeme: "007", line: 1 },
+            Token { token: TokenType::Number(1.0), lexeme: "1.0000", line: 1 },
+            Token { token: TokenType::Eof, lexeme: "", line: 1 }
+        ]);
+        assert_eq!(tokens.iter().map(|token| format!("{}", token)).collect::<Vec<String>>(), vec![
+            "NUMBER 007 7.0",
+            "NUMBER 1.0000 1.0",
+            "EOF  null",
+        ]);
+    }
+
     #[test]
     fn test_lexer_literal_identifier() {
         let source = "tomato apple nuts1 deez_nuts _test";
@@ -375,18 +785,20 @@ mod tests {
 
     #[test]
     fn test_lexer_literal_keywords() {
-        let source = "and class else false for fun if nil or print return super this true var while";
+        let source = "and break class else false for fun global if nil or print return super this true var while";
         let mut scanner = Scanner::new(source);
         let (tokens, errors) = scanner.scan_tokens();
 
         assert!(errors.is_empty());
         assert_eq!(tokens, vec![
             Token { token: TokenType::And, lexeme: "and", line: 1 },
+            Token { token: TokenType::Break, lexeme: "break", line: 1 },
             Token { token: TokenType::Class, lexeme: "class", line: 1 },
             Token { token: TokenType::Else, lexeme: "else", line: 1 },
             Token { token: TokenType::False, lexeme: "false", line: 1 },
             Token { token: TokenType::For, lexeme: "for", line: 1 },
             Token { token: TokenType::Fun, lexeme: "fun", line: 1 },
+            Token { token: TokenType::Global, lexeme: "global", line: 1 },
             Token { token: TokenType::If, lexeme: "if", line: 1 },
             Token { token: TokenType::Nil, lexeme: "nil", line: 1 },
             Token { token: TokenType::Or, lexeme: "or", line: 1 },
@@ -415,6 +827,111 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_lexer_comment_last_line() {
+        let source = "1\n2 // trailing";
+        let mut scanner = Scanner::new(source);
+        let (tokens, errors) = scanner.scan_tokens();
+
+        assert!(errors.is_empty());
+        assert_eq!(tokens, vec![
+            Token { token: TokenType::Number(1.0), lexeme: "1", line: 1 },
+            Token { token: TokenType::Number(2.0), lexeme: "2", line: 2 },
+            Token { token: TokenType::Eof, lexeme: "", line: 2 }
+        ]);
+    }
+
+    #[test]
+    fn test_lexer_block_comment() {
+        let source = "1 /* one\ntwo * / three\n*/ 2 /**/ 3\n// line\n/* a */ 4";
+        let mut scanner = Scanner::new(source);
+        let (tokens, errors) = scanner.scan_tokens();
+
+        assert!(errors.is_empty());
+        assert_eq!(tokens, vec![
+            Token { token: TokenType::Number(1.0), lexeme: "1", line: 1 },
+            Token { token: TokenType::Number(2.0), lexeme: "2", line: 3 },
+            Token { token: TokenType::Number(3.0), lexeme: "3", line: 3 },
+            Token { token: TokenType::Number(4.0), lexeme: "4", line: 5 },
+            Token { token: TokenType::Eof, lexeme: "", line: 5 }
+        ]);
+    }
+
+    #[rstest]
+    #[case("/* one\ntwo\nthree */ @", vec!["[line 3] Error: Unexpected character: @"])]
+    #[case("// one\n// two\n\n@", vec!["[line 4] Error: Unexpected character: @"])]
+    #[case("/* one\ntwo", vec!["[line 2] Error: Unterminated block comment."])]
+    #[case("/* one */ \"open\n", vec!["[line 2] Error: Unterminated string."])]
+    fn test_lexer_errors_after_comments(#[case] input: &str, #[case] expected: Vec<&str>) {
+        let mut scanner = Scanner::new(input);
+        let (_, errors) = scanner.scan_tokens();
+
+        assert_eq!(expected, errors);
+    }
+
+    #[test]
+    fn test_lexer_line_continuation() {
+        let source = "var a = 1 + \\\n  2 \\\r\n;\\\nprint \"x\\\ny\";";
+        let mut scanner = Scanner::new(source);
+        let (tokens, errors) = scanner.scan_tokens();
+
+        assert!(errors.is_empty());
+        assert_eq!(tokens, vec![
+            Token { token: TokenType::Var, lexeme: "var", line: 1 },
+            Token { token: TokenType::Identifier("a"), lexeme: "a", line: 1 },
+            Token { token: TokenType::Equal, lexeme: "=", line: 1 },
+            Token { token: TokenType::Number(1.0), lexeme: "1", line: 1 },
+            Token { token: TokenType::Plus, lexeme: "+", line: 1 },
+            Token { token: TokenType::Number(2.0), lexeme: "2", line: 2 },
+            Token { token: TokenType::Semicolon, lexeme: ";", line: 3 },
+            Token { token: TokenType::Print, lexeme: "print", line: 4 },
+            Token { token: TokenType::String("x\\\ny"), lexeme: "\"x\\\ny\"", line: 4 },
+            Token { token: TokenType::Semicolon, lexeme: ";", line: 5 },
+            Token { token: TokenType::Eof, lexeme: "", line: 5 }
+        ]);
+    }
+
+    #[rstest]
+    #[case("1 \\ 2", vec!["[line 1] Error: Unexpected character: \\"])]
+    #[case("1 \\", vec!["[line 1] Error: Unexpected character: \\"])]
+    #[case("\\\n\\ \n", vec!["[line 2] Error: Unexpected character: \\"])]
+    fn test_lexer_line_continuation_errors(#[case] input: &str, #[case] expected: Vec<&str>) {
+        let mut scanner = Scanner::new(input);
+        let (_, errors) = scanner.scan_tokens();
+
+        assert_eq!(expected, errors);
+    }
+
+    #[test]
+    fn test_lexer_interpolation() {
+        let source = "\"a ${x + {}} b ${\"c\"} \\${d}\"";
+        let mut scanner = Scanner::new(source);
+        let (tokens, errors) = scanner.scan_tokens();
+
+        assert!(errors.is_empty());
+        assert_eq!(tokens, vec![
+            Token { token: TokenType::Interpolation("a "), lexeme: "\"a ${", line: 1 },
+            Token { token: TokenType::Identifier("x"), lexeme: "x", line: 1 },
+            Token { token: TokenType::Plus, lexeme: "+", line: 1 },
+            Token { token: TokenType::LeftBrace, lexeme: "{", line: 1 },
+            Token { token: TokenType::RightBrace, lexeme: "}", line: 1 },
+            Token { token: TokenType::Interpolation(" b "), lexeme: "} b ${", line: 1 },
+            Token { token: TokenType::String("c"), lexeme: "\"c\"", line: 1 },
+            Token { token: TokenType::String(" \\${d}"), lexeme: "} \\${d}\"", line: 1 },
+            Token { token: TokenType::Eof, lexeme: "", line: 1 }
+        ]);
+    }
+
+    #[rstest]
+    #[case("\"a ${1", vec!["[line 1] Error: Unterminated interpolation."])]
+    #[case("\"a ${1} b", vec!["[line 1] Error: Unterminated string."])]
+    fn test_lexer_interpolation_errors(#[case] input: &str, #[case] expected: Vec<&str>) {
+        let mut scanner = Scanner::new(input);
+        let (_, errors) = scanner.scan_tokens();
+
+        assert_eq!(expected, errors);
+    }
+
     #[test]
     fn test_lexer_and_token_to_string() {
         let source = "\"test\" 123 123.123 asdf ==";
@@ -439,4 +956,14 @@ mod tests {
             "EOF  null"
         ]);
     }
+
+    #[test]
+    fn test_keywords() {
+        let keywords = keywords();
+
+        assert!(keywords.contains(&"fun"));
+        assert!(keywords.contains(&"class"));
+        assert!(keywords.contains(&"while"));
+        assert_eq!(18, keywords.len());
+    }
 }
\ No newline at end of file
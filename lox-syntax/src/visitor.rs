@@ -0,0 +1,141 @@
+use crate::expression::Expression;
+use crate::statement::Statement;
+
+pub trait ExpressionVisitor {
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression);
+    }
+}
+
+pub trait StatementVisitor: ExpressionVisitor {
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+    }
+}
+
+pub fn walk_expression<V: ExpressionVisitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        // Function bodies are statements, visitors that need them handle the function expression themselves
+        Expression::Literal(_, _) | Expression::Variable(_, _) | Expression::Increment(_, _, _, _) | Expression::Function(_, _, _) => {},
+        Expression::Grouping(expression, _) | Expression::Unary(_, expression, _) | Expression::Assign(_, expression, _) => {
+            visitor.visit_expression(expression);
+        },
+        Expression::Binary(_, left, right, _) | Expression::And(left, right, _) | Expression::Or(left, right, _) => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        },
+        Expression::Call(callee, arguments, _) => {
+            visitor.visit_expression(callee);
+
+            for argument in arguments {
+                visitor.visit_expression(argument);
+            }
+        },
+        Expression::Interpolation(parts, _) => {
+            for part in parts {
+                visitor.visit_expression(part);
+            }
+        },
+    }
+}
+
+pub fn walk_statement<V: StatementVisitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::Expression(expression) | Statement::Global(_, expression) => visitor.visit_expression(expression),
+        Statement::Print(expression, fallback) => {
+            visitor.visit_expression(expression);
+
+            if let Some(fallback) = fallback {
+                visitor.visit_expression(fallback);
+            }
+        },
+        Statement::Variable(_, expression) | Statement::Return(expression, _) => {
+            if let Some(expression) = expression {
+                visitor.visit_expression(expression);
+            }
+        },
+        Statement::Block(statements) => {
+            for statement in statements {
+                visitor.visit_statement(statement);
+            }
+        },
+        Statement::If(condition, if_body, else_body) => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(if_body);
+
+            if let Some(else_body) = else_body {
+                visitor.visit_statement(else_body);
+            }
+        },
+        Statement::While(condition, body, else_body) => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(body);
+
+            if let Some(else_body) = else_body {
+                visitor.visit_statement(else_body);
+            }
+        },
+        Statement::For(initial, condition, incrementer, body, else_body) => {
+            if let Some(initial) = initial {
+                visitor.visit_statement(initial);
+            }
+
+            if let Some(condition) = condition {
+                visitor.visit_expression(condition);
+            }
+
+            if let Some(incrementer) = incrementer {
+                visitor.visit_expression(incrementer);
+            }
+
+            visitor.visit_statement(body);
+
+            if let Some(else_body) = else_body {
+                visitor.visit_statement(else_body);
+            }
+        },
+        Statement::Function(_, _, body) => visitor.visit_statement(body),
+        Statement::Break => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::Expression;
+    use crate::parser::Parser;
+    use crate::tokenizer::Scanner;
+    use crate::visitor::{walk_expression, ExpressionVisitor, StatementVisitor};
+
+    #[derive(Default)]
+    struct CallCounter {
+        count: usize,
+    }
+
+    impl ExpressionVisitor for CallCounter {
+        fn visit_expression(&mut self, expression: &Expression) {
+            if let Expression::Call(_, _, _) = expression {
+                self.count += 1;
+            }
+
+            walk_expression(self, expression);
+        }
+    }
+
+    impl StatementVisitor for CallCounter {}
+
+    #[test]
+    fn test_visitor_counts_calls() {
+        let source = "fun f(a) { return a; } print f(f(1)); for (var i = 0; i < f(2); i = i + 1) { clock(); } else f(3)(4);";
+        let mut scanner = Scanner::new(source);
+        let (tokens, _) = scanner.scan_tokens();
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse().unwrap();
+
+        let mut counter = CallCounter::default();
+        for statement in statements.iter() {
+            counter.visit_statement(statement);
+        }
+
+        assert_eq!(6, counter.count);
+    }
+}
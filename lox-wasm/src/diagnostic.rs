@@ -0,0 +1,61 @@
+use js_sys::{Object, Reflect};
+use lox_syntax::parser::ParseError;
+use lox_syntax::tokenizer::ScanError;
+use wasm_bindgen::JsValue;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Scan,
+    Parse,
+    Runtime,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Scan => "scan",
+            Stage::Parse => "parse",
+            Stage::Runtime => "runtime",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub stage: Stage,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(stage: Stage, line: Option<usize>, message: String) -> Self {
+        Diagnostic { stage, line, message }
+    }
+
+    pub fn from_scan_error(error: &ScanError) -> Self {
+        Diagnostic::new(Stage::Scan, Some(error.line), error.kind.to_string())
+    }
+
+    pub fn from_parse_error(error: &ParseError) -> Self {
+        Diagnostic::new(Stage::Parse, Some(error.line), error.located_message())
+    }
+
+    /// Runtime errors only carry a message, any line is part of its text.
+    pub fn from_runtime_error(error: &str) -> Self {
+        Diagnostic::new(Stage::Runtime, None, error.to_string())
+    }
+
+    pub fn to_js_value(&self) -> Result<JsValue, JsValue> {
+        let object = Object::new();
+        let line = match self.line {
+            Some(line) => JsValue::from_f64(line as f64),
+            None => JsValue::NULL,
+        };
+
+        Reflect::set(&object, &JsValue::from_str("stage"), &JsValue::from_str(self.stage.as_str()))?;
+        Reflect::set(&object, &JsValue::from_str("line"), &line)?;
+        Reflect::set(&object, &JsValue::from_str("message"), &JsValue::from_str(&self.message))?;
+
+        Ok(object.into())
+    }
+}
@@ -1,37 +1,121 @@
+mod diagnostic;
+mod token;
 mod utils;
 
 use lox_syntax::tokenizer::Scanner;
 use wasm_bindgen::prelude::*;
-use js_sys::Function;
+use js_sys::{Array, Function};
 use lox_runtime::interpreter::Interpreter;
 use lox_syntax::parser::Parser;
+use lox_syntax::statement::Statement;
+use lox_syntax::token::Token;
+use crate::diagnostic::Diagnostic;
 
 #[wasm_bindgen]
-pub fn run(code: &str, print: Function) -> Result<(), String> {
+pub fn run(code: &str, print: Function) -> Result<(), JsValue> {
+    utils::set_panic_hook();
+
+    let statements = match compile(code) {
+        Ok(statements) => statements,
+        Err(diagnostics) => return Err(to_js_array(&diagnostics)?),
+    };
+
+    let mut interpreter = Interpreter::new(|value| {
+        print.call1(&JsValue::NULL, &JsValue::from_str(&value)).unwrap();
+    });
+
+    match interpreter.run(&statements) {
+        Ok(()) => Ok(()),
+        Err(error) => Err(to_js_array(&[Diagnostic::from_runtime_error(&error)])?),
+    }
+}
+
+#[wasm_bindgen]
+pub fn tokenize(code: &str) -> Result<JsValue, JsValue> {
+    utils::set_panic_hook();
+
+    match scan(code) {
+        Ok(tokens) => token::to_js_array(&tokens),
+        Err(diagnostics) => Err(to_js_array(&diagnostics)?),
+    }
+}
+
+fn scan(code: &str) -> Result<Vec<String>, Vec<Diagnostic>> {
     let mut scanner = Scanner::new(code);
-    let (tokens, errors) = scanner.scan_tokens();
+    let (tokens, errors) = scanner.scan_structured();
 
     if !errors.is_empty() {
-        return Err(errors.iter().map(|error| error.to_string()).collect::<Vec<String>>().join("\n"));
+        return Err(errors.iter().map(Diagnostic::from_scan_error).collect());
     }
 
-    let mut parser = Parser::new(tokens);
-    let statements = parser.parse();
-
-    if statements.is_ok() {
-        let mut interpreter = Interpreter::new(|value| {    
-            print.call1(&JsValue::NULL, &JsValue::from_str(&value)).unwrap();
-        });
-        
-        let result = interpreter.run(&statements.unwrap());
-
-        if result.is_ok() {
-            Ok(())
-        } else {
-            Err(result.err().unwrap())
-        }
-    } else {
-        Err(statements.err().unwrap())
+    Ok(tokens.iter().map(Token::to_json).collect())
+}
+
+fn compile(code: &str) -> Result<Vec<Statement>, Vec<Diagnostic>> {
+    let mut scanner = Scanner::new(code);
+    let (tokens, errors) = scanner.scan_structured();
+
+    if !errors.is_empty() {
+        return Err(errors.iter().map(Diagnostic::from_scan_error).collect());
     }
+
+    let mut parser = Parser::new(tokens);
+    parser.parse().map_err(|error| vec![Diagnostic::from_parse_error(&error)])
 }
 
+fn to_js_array(diagnostics: &[Diagnostic]) -> Result<JsValue, JsValue> {
+    Ok(diagnostics.iter().map(Diagnostic::to_js_value).collect::<Result<Array, JsValue>>()?.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{compile, scan};
+    use crate::diagnostic::{Diagnostic, Stage};
+
+    #[test]
+    fn test_scan_tokens() {
+        let tokens = scan("var a = 1.5;\nprint \"hi ${a}\";").unwrap();
+
+        assert_eq!(vec![
+            r#"{"type": "VAR", "lexeme": "var", "line": 1, "value": null}"#,
+            r#"{"type": "IDENTIFIER", "lexeme": "a", "line": 1, "value": null}"#,
+            r#"{"type": "EQUAL", "lexeme": "=", "line": 1, "value": null}"#,
+            r#"{"type": "NUMBER", "lexeme": "1.5", "line": 1, "value": 1.5}"#,
+            r#"{"type": "SEMICOLON", "lexeme": ";", "line": 1, "value": null}"#,
+            r#"{"type": "PRINT", "lexeme": "print", "line": 2, "value": null}"#,
+            r#"{"type": "INTERPOLATION", "lexeme": "\"hi ${", "line": 2, "value": "hi "}"#,
+            r#"{"type": "IDENTIFIER", "lexeme": "a", "line": 2, "value": null}"#,
+            r#"{"type": "STRING", "lexeme": "}\"", "line": 2, "value": ""}"#,
+            r#"{"type": "SEMICOLON", "lexeme": ";", "line": 2, "value": null}"#,
+            r#"{"type": "EOF", "lexeme": "", "line": 2, "value": null}"#,
+        ], tokens);
+    }
+
+    #[test]
+    fn test_scan_error() {
+        let diagnostics = scan("print \"open").err().unwrap();
+
+        assert_eq!(vec![Diagnostic::new(Stage::Scan, Some(1), "Unterminated string.".to_string())], diagnostics);
+    }
+
+    #[test]
+    fn test_compile_scan_error() {
+        let diagnostics = compile("print 1;\nprint @;").err().unwrap();
+
+        assert_eq!(vec![Diagnostic::new(Stage::Scan, Some(2), "Unexpected character: @".to_string())], diagnostics);
+    }
+
+    #[test]
+    fn test_compile_parse_error() {
+        let diagnostics = compile("print 1;\n\nprint 1 +;").err().unwrap();
+
+        assert_eq!(vec![Diagnostic::new(Stage::Parse, Some(3), "Error at ';': Expect expression.".to_string())], diagnostics);
+    }
+
+    #[test]
+    fn test_diagnostic_without_line() {
+        let diagnostic = Diagnostic::from_runtime_error("Operands must be numbers.");
+
+        assert_eq!(Diagnostic::new(Stage::Runtime, None, "Operands must be numbers.".to_string()), diagnostic);
+    }
+}
@@ -0,0 +1,7 @@
+use js_sys::JSON;
+use wasm_bindgen::JsValue;
+
+/// Turns the JSON of each token, as `Token::to_json` writes it for the CLI, into an array of objects.
+pub fn to_js_array(tokens: &[String]) -> Result<JsValue, JsValue> {
+    JSON::parse(&format!("[{}]", tokens.join(", ")))
+}
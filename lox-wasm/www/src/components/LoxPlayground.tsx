@@ -15,6 +15,12 @@ type Message = {
   occurredAt: Date,
 };
 
+type Diagnostic = {
+  stage: "scan" | "parse" | "runtime",
+  line: number | null,
+  message: string,
+};
+
 function LoxPlayground() {
   const [messages, setMessages] = useState<Message[]>([])
   const [code, setCode] = useState(examples[0].code)
@@ -27,7 +33,12 @@ function LoxPlayground() {
       );
     } catch (exception: unknown) {
       console.error(exception);
-      if (exception instanceof Error) {
+      if (Array.isArray(exception)) {
+        for (const diagnostic of exception as Diagnostic[]) {
+          const location = diagnostic.line === null ? "" : `[line ${diagnostic.line}] `;
+          emittedMessages.push({id: crypto.randomUUID(), type: MessageType.Error, text: location + diagnostic.message, occurredAt: new Date()});
+        }
+      } else if (exception instanceof Error) {
         emittedMessages.push({id: crypto.randomUUID(), type: MessageType.Error, text: exception.message, occurredAt: new Date()});
       } else if (typeof exception === "string") {
         emittedMessages.push({id: crypto.randomUUID(), type: MessageType.Error, text: exception, occurredAt: new Date()});
@@ -4,20 +4,27 @@
   monaco.languages.setMonarchTokensProvider("lox", {
     tokenizer: {
       root: [
-        [/\b(and|class|else|false|for|fun|if|nil|or|print|return|super|this|true|var|while)\b/, "keyword"],
+        [/\b(and|break|class|else|false|for|fun|global|if|nil|or|print|return|super|this|true|var|while)\b/, "keyword"],
         [/".*?"/, "string"],
         [/\d+(\.\d+)?/, "number"],
         [/\/\/.*$/, "comment"],
+        [/\/\*/, "comment", "@comment"],
         [/[+\-*\/=!<>]=?|and|or/, "operator"],
         [/[a-zA-Z_]\w*/, "identifier"],
         [/[{}()]/, "delimiter"],
       ],
+      comment: [
+        [/\*\//, "comment", "@pop"],
+        [/[^*]+/, "comment"],
+        [/\*/, "comment"],
+      ],
     },
   });
 
   monaco.languages.setLanguageConfiguration("lox", {
     comments: {
       lineComment: "//",
+      blockComment: ["/*", "*/"],
     },
     brackets: [
       ["{", "}"],